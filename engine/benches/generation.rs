@@ -0,0 +1,63 @@
+// Times the generation step on big random boards for an increasing number of threads.
+// Run with `cargo bench -p fight-for-your-life-engine`.
+
+use std::time::Instant;
+
+use fight_for_your_life_engine::{calculate_next_generation_parallel, Board, PlayerNum, Rule};
+
+const GENERATIONS: usize = 50;
+const SIZES: [(usize, usize); 3] = [(512, 512), (2048, 2048), (4096, 4096)];
+
+fn main() {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut thread_counts = vec![1, 2, 4, 8, 16];
+    thread_counts.retain(|threads| *threads <= cores.max(4));
+    println!("{} cores available, {} generations per run", cores, GENERATIONS);
+
+    for (width, height) in SIZES.iter() {
+        let start_board = random_board(*width, *height);
+        let mut single_threaded = None;
+        let mut single_threaded_time = 0.0;
+
+        for threads in thread_counts.iter() {
+            let mut board = start_board.clone();
+            let mut next = Board::new(*width, *height);
+            let start = Instant::now();
+            for _ in 0..GENERATIONS {
+                calculate_next_generation_parallel(&board, &mut next, &Rule::default(), *threads);
+                std::mem::swap(&mut board, &mut next);
+            }
+            let seconds = start.elapsed().as_secs_f64();
+
+            match &single_threaded {
+                None => {
+                    single_threaded = Some(board);
+                    single_threaded_time = seconds;
+                },
+                Some(expected) => assert!(*expected == board, "{} threads gave a different board", threads)
+            }
+            println!("{:>5}x{:<5} {:>2} threads: {:>8.3} ms/generation, {:.2}x",
+                     width, height, threads, seconds * 1000.0 / GENERATIONS as f64, single_threaded_time / seconds);
+        }
+    }
+}
+
+// A board with about a third of its cells alive, split between the players, from a fixed seed.
+fn random_board(width: usize, height: usize) -> Board {
+    let mut board = Board::new(width, height);
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    for y in 0..height {
+        for x in 0..width {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            match seed % 6 {
+                0 => board.set(x, y, Some(PlayerNum::ONE)),
+                1 => board.set(x, y, Some(PlayerNum::TWO)),
+                _ => ()
+            }
+        }
+    }
+
+    board
+}
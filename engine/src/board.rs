@@ -0,0 +1,445 @@
+use crate::player::PlayerNum;
+use crate::rules::Rule;
+use crate::topology::{Grid, Topology};
+
+// A cell is either dead, or alive and owned by one of the players.
+pub type Cell = Option<PlayerNum>;
+
+// Offsets of the 8 neighbours of a cell, clockwise starting from the one above it.
+pub(crate) const NEIGHBOUR_OFFSETS: [(isize, isize); 8] = [(0,-1), (1,-1), (1,0), (1,1), (0,1), (-1,1), (-1,0), (-1,-1)];
+
+// Bands of fewer rows are not worth a thread of their own.
+const MIN_ROWS_PER_THREAD: usize = 16;
+
+// A board of `width` x `height` blocks, bit-packed row by row: bit x % 64 of word x / 64 of a row is the cell at x.
+// `alive` holds which cells are alive, `owner` which alive cells belong to player 2. Bits past the width are always 0.
+// `grid` is the shape of the cells and `topology` how the edges of the board connect, both used to look up neighbours.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Board {
+    width: usize,
+    height: usize,
+    grid: Grid,
+    topology: Topology,
+    words_per_row: usize,
+    alive: Vec<u64>,
+    owner: Vec<u64>
+}
+
+impl Board {
+    pub fn new(width: usize, height: usize) -> Board {
+        Board::with_topology(width, height, Topology::BOUNDED)
+    }
+
+    pub fn with_topology(width: usize, height: usize, topology: Topology) -> Board {
+        Board::with_shape(width, height, Grid::SQUARE, topology)
+    }
+
+    pub fn with_shape(width: usize, height: usize, grid: Grid, topology: Topology) -> Board {
+        let words_per_row = width.div_ceil(64);
+        Board {
+            width,
+            height,
+            grid,
+            topology,
+            words_per_row,
+            alive: vec![0; words_per_row * height],
+            owner: vec![0; words_per_row * height]
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn grid(&self) -> Grid {
+        self.grid
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Cell {
+        let (index, bit) = self.position(x, y);
+        if self.alive[index] & bit == 0 {
+            None
+        } else if self.owner[index] & bit == 0 {
+            Some(PlayerNum::ONE)
+        } else {
+            Some(PlayerNum::TWO)
+        }
+    }
+
+    pub fn is_alive(&self, x: usize, y: usize) -> bool {
+        let (index, bit) = self.position(x, y);
+        self.alive[index] & bit != 0
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        let (index, bit) = self.position(x, y);
+        match cell {
+            None => {
+                self.alive[index] &= !bit;
+                self.owner[index] &= !bit;
+            },
+            Some(PlayerNum::ONE) => {
+                self.alive[index] |= bit;
+                self.owner[index] &= !bit;
+            },
+            Some(PlayerNum::TWO) => {
+                self.alive[index] |= bit;
+                self.owner[index] |= bit;
+            }
+        }
+    }
+
+    // How many alive cells the player owns.
+    pub fn count_owned(&self, player_num: &PlayerNum) -> usize {
+        self.alive.iter().zip(self.owner.iter())
+            .map(|(alive, owner)| match player_num {
+                PlayerNum::ONE => alive & !owner,
+                PlayerNum::TWO => alive & owner
+            }.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn count_alive(&self) -> usize {
+        self.alive.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    // Whether no cell in the `width` x `height` region starting at (x0, y0) is alive. The region must be inside the board.
+    pub fn is_region_dead(&self, x0: usize, y0: usize, width: usize, height: usize) -> bool {
+        let (first_word, last_word) = (x0 / 64, (x0 + width - 1) / 64);
+        (y0..y0 + height).all(|y| {
+            (first_word..=last_word).all(|i| {
+                let word = self.alive[y * self.words_per_row + i];
+                let from = if i == first_word {x0 % 64} else {0};
+                let to = if i == last_word {(x0 + width - 1) % 64} else {63};
+                let mask = (!0u64 >> (63 - to)) & (!0u64 << from);
+                word & mask == 0
+            })
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.alive.iter_mut().for_each(|word| *word = 0);
+        self.owner.iter_mut().for_each(|word| *word = 0);
+    }
+
+    fn position(&self, x: usize, y: usize) -> (usize, u64) {
+        debug_assert!(x < self.width && y < self.height);
+        (y * self.words_per_row + x / 64, 1 << (x % 64))
+    }
+
+    fn alive_row(&self, y: usize) -> &[u64] {
+        &self.alive[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    // The bits of the last word of a row that are inside the board.
+    fn last_word_mask(&self) -> u64 {
+        match self.width % 64 {
+            0 => !0,
+            bits => (1 << bits) - 1
+        }
+    }
+}
+
+// With the default rule, B3/S23:
+//1) Any live cell with fewer than two live neighbours dies, as if by underpopulation.
+//2) Any live cell with two or three live neighbours lives on to the next generation.
+//3) Any live cell with more than three live neighbours dies, as if by overpopulation.
+//4) Any dead cell with exactly three live neighbours becomes a live cell, as if by reproduction.
+// Surviving cells keep their owner, newborn cells belong to the owner of most of their parents.
+pub fn calculate_next_generation(board: &Board, rule: &Rule) -> Board {
+    let mut next_gen_board = Board::with_shape(board.width(), board.height(), board.grid(), board.topology());
+    calculate_next_generation_into(board, &mut next_gen_board, rule);
+    next_gen_board
+}
+
+// Like `calculate_next_generation`, but writes into `next`, so two boards can be swapped back and forth
+// instead of allocating one for every generation. `next` must have the same dimensions as `board`.
+pub fn calculate_next_generation_into(board: &Board, next: &mut Board, rule: &Rule) {
+    calculate_next_generation_parallel(board, next, rule, 1)
+}
+
+// Like `calculate_next_generation_into`, splitting the board in bands of rows calculated on up to `threads` threads.
+// Every row only depends on `board`, so the result is the same whatever the number of threads.
+pub fn calculate_next_generation_parallel(board: &Board, next: &mut Board, rule: &Rule, threads: usize) {
+    assert!(board.width == next.width && board.height == next.height, "boards of different dimensions");
+    next.grid = board.grid;
+    next.topology = board.topology;
+    if board.words_per_row == 0 || board.height == 0 {return}
+
+    let threads = threads.clamp(1, (board.height / MIN_ROWS_PER_THREAD).max(1));
+    if threads == 1 {
+        calculate_next_band(board, 0, rule, &mut next.alive, &mut next.owner);
+        return
+    }
+
+    let rows_per_band = board.height.div_ceil(threads);
+    let words_per_band = rows_per_band * board.words_per_row;
+    std::thread::scope(|scope| {
+        let bands = next.alive.chunks_mut(words_per_band).zip(next.owner.chunks_mut(words_per_band));
+        for (band, (alive, owner)) in bands.enumerate() {
+            scope.spawn(move || calculate_next_band(board, band * rows_per_band, rule, alive, owner));
+        }
+    });
+}
+
+// Calculates the rows of the next generation starting at `first_row`, as many as fit in `next_alive`.
+fn calculate_next_band(board: &Board, first_row: usize, rule: &Rule, next_alive: &mut [u64], next_owner: &mut [u64]) {
+    let rows = next_alive.chunks_mut(board.words_per_row).zip(next_owner.chunks_mut(board.words_per_row));
+    for (i, (alive, owner)) in rows.enumerate() {
+        calculate_next_row(board, first_row + i, rule, alive, owner);
+    }
+}
+
+// Calculates row `y` of the next generation 64 cells at a time: the 8 neighbours of every cell in a word
+// are added up with a bit-sliced adder, so each of the 4 bits of every count lives in its own word.
+// The neighbouring rows and columns past the edges come from the same lookup as `neighbours`.
+fn calculate_next_row(board: &Board, y: usize, rule: &Rule, next_alive: &mut [u64], next_owner: &mut [u64]) {
+    let row_lookup = |dy| board.topology.neighbour_row(y, dy, board.height).map(|ny| board.alive_row(ny));
+    let (above, row, below) = (row_lookup(-1), row_lookup(0), row_lookup(1));
+    let row_start = y * board.words_per_row;
+
+    for i in 0..board.words_per_row {
+        let (above_w, above_c, above_e) = neighbour_words(above, i, board);
+        let (row_w, alive, row_e) = neighbour_words(row, i, board);
+        let (below_w, below_c, below_e) = neighbour_words(below, i, board);
+        let counts = match (board.grid, y.is_multiple_of(2)) {
+            (Grid::SQUARE, _) => add_eight(above_w, above_c, above_e, row_w, row_e, below_w, below_c, below_e),
+            // the rows above and below an even row are shifted right, so its diagonal neighbours are to the west
+            (Grid::HEX, true) => add_eight(above_w, above_c, 0, row_w, row_e, below_w, below_c, 0),
+            (Grid::HEX, false) => add_eight(0, above_c, above_e, row_w, row_e, 0, below_c, below_e)
+        };
+
+        let mut next = 0;
+        for n in 0..=8 {
+            let (born, survives) = (rule.is_born(n), rule.survives(n));
+            if !born && !survives {continue}
+
+            let has_n = count_equals(&counts, n);
+            if born {next |= has_n & !alive}
+            if survives {next |= has_n & alive}
+        }
+        if i + 1 == board.words_per_row {
+            next &= board.last_word_mask();
+        }
+
+        // survivors keep their owner, newborns are sparse so their owner is worked out one by one
+        let mut owner = next & alive & board.owner[row_start + i];
+        let mut born = next & !alive;
+        while born != 0 {
+            let bit = born.trailing_zeros() as usize;
+            if newborn_owner(i * 64 + bit, y, board) == Some(PlayerNum::TWO) {
+                owner |= 1 << bit;
+            }
+            born &= born - 1;
+        }
+
+        next_alive[i] = next;
+        next_owner[i] = owner;
+    }
+}
+
+// For word `i` of a row, the words holding the west neighbour, the cell itself and the east neighbour of each of its cells.
+// Rows outside of the board are dead. Past the first and the last column, the board's topology decides what is there.
+fn neighbour_words(row: Option<&[u64]>, i: usize, board: &Board) -> (u64, u64, u64) {
+    let row = match row {
+        Some(row) => row,
+        None => return (0, 0, 0)
+    };
+    let bit_at = |x: Option<usize>| x.map_or(0, |x| (row[x / 64] >> (x % 64)) & 1);
+    let last_x = board.width - 1;
+
+    let mut west = row[i] << 1;
+    if i > 0 {
+        west |= row[i - 1] >> 63;
+    } else {
+        west |= bit_at(board.topology.neighbour_column(0, -1, board.width));
+    }
+    let mut east = row[i] >> 1;
+    if i + 1 < row.len() {
+        east |= row[i + 1] << 63;
+    } else {
+        east |= bit_at(board.topology.neighbour_column(last_x, 1, board.width)) << (last_x % 64);
+    }
+
+    (west, row[i], east)
+}
+
+// Adds up 8 words bit by bit, returning the 4 bits of each sum from the least significant.
+#[allow(clippy::too_many_arguments)]
+fn add_eight(a: u64, b: u64, c: u64, d: u64, e: u64, f: u64, g: u64, h: u64) -> [u64; 4] {
+    let (abc, abc_carry) = full_add(a, b, c);
+    let (def, def_carry) = full_add(d, e, f);
+    let (gh, gh_carry) = (g ^ h, g & h);
+    let (ones, ones_carry) = full_add(abc, def, gh);
+    let (twos_partial, twos_carry_1) = full_add(abc_carry, def_carry, gh_carry);
+    let (twos, twos_carry_2) = (twos_partial ^ ones_carry, twos_partial & ones_carry);
+    let (fours, eights) = (twos_carry_1 ^ twos_carry_2, twos_carry_1 & twos_carry_2);
+
+    [ones, twos, fours, eights]
+}
+
+fn full_add(a: u64, b: u64, c: u64) -> (u64, u64) {
+    let partial = a ^ b;
+    (partial ^ c, (a & b) | (partial & c))
+}
+
+// The bits whose count is `n`.
+fn count_equals(counts: &[u64; 4], n: usize) -> u64 {
+    counts.iter().enumerate().fold(!0, |result, (bit, word)| {
+        if n & (1 << bit) != 0 {result & word} else {result & !word}
+    })
+}
+
+// The neighbours of (x, y) on the board's grid, clockwise. Neighbours past an edge that does not wrap are dead.
+pub fn neighbours(x: usize, y: usize, board: &Board) -> impl Iterator<Item = Cell> + '_ {
+    board.grid.neighbour_offsets(y).iter().map(move |(dx, dy)| {
+        match board.topology.neighbour(x, y, *dx, *dy, board.width, board.height) {
+            Some((nx, ny)) => board.get(nx, ny),
+            None => None
+        }
+    })
+}
+
+// The player owning most of the alive neighbours of (x, y).
+// Ties go to the owner of the first alive neighbour, clockwise from the one above (the upper right one on a hex grid).
+pub fn newborn_owner(x: usize, y: usize, board: &Board) -> Cell {
+    majority_owner(neighbours(x, y, board))
+}
+
+// The owner of most of the given cells, or of the first alive one on ties. `None` when none is alive.
+pub(crate) fn majority_owner(cells: impl Iterator<Item = Cell>) -> Cell {
+    let (mut count_1, mut count_2, mut first) = (0, 0, None);
+    for cell in cells {
+        match cell {
+            Some(PlayerNum::ONE) => count_1 += 1,
+            Some(PlayerNum::TWO) => count_2 += 1,
+            None => continue
+        }
+        if first.is_none() {
+            first = cell;
+        }
+    }
+
+    match count_1.cmp(&count_2) {
+        std::cmp::Ordering::Greater => Some(PlayerNum::ONE),
+        std::cmp::Ordering::Less => Some(PlayerNum::TWO),
+        std::cmp::Ordering::Equal => first
+    }
+}
+
+pub fn count_alive_neighbours(x: usize, y: usize, board: &Board) -> usize {
+    neighbours(x, y, board).filter(Option::is_some).count()
+}
+
+// A small xorshift generator, so the random boards of the tests are the same on every run.
+#[cfg(test)]
+pub(crate) struct Random(pub(crate) u64);
+
+#[cfg(test)]
+impl Random {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+// A board with about 2 in 5 cells alive, split between the players.
+#[cfg(test)]
+pub(crate) fn random_board(width: usize, height: usize, grid: Grid, topology: Topology, random: &mut Random) -> Board {
+    let mut board = Board::with_shape(width, height, grid, topology);
+    for y in 0..height {
+        for x in 0..width {
+            match random.next() % 5 {
+                0 => board.set(x, y, Some(PlayerNum::ONE)),
+                1 => board.set(x, y, Some(PlayerNum::TWO)),
+                _ => ()
+            }
+        }
+    }
+    board
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The next generation worked out one cell at a time from its neighbours.
+    fn reference_generation(board: &Board, rule: &Rule) -> Board {
+        let mut next = Board::with_shape(board.width(), board.height(), board.grid(), board.topology());
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                let alive = board.is_alive(x, y);
+                if !rule.next_state(alive, count_alive_neighbours(x, y, board)) {continue}
+                next.set(x, y, if alive {board.get(x, y)} else {newborn_owner(x, y, board)});
+            }
+        }
+        next
+    }
+
+    #[test]
+    fn word_parallel_generations_match_the_per_cell_rules() {
+        let mut random = Random(0x9e3779b97f4a7c15);
+        let rules = [Rule::CONWAY, Rule::HEXLIFE, Rule::parse("highlife").unwrap(), Rule::parse("day_and_night").unwrap()];
+        for grid in [Grid::SQUARE, Grid::HEX].iter() {
+            for topology in [Topology::BOUNDED, Topology::CYLINDER, Topology::TORUS].iter() {
+                for (width, height) in [(1, 2), (5, 4), (63, 18), (64, 34), (65, 40), (130, 50)].iter() {
+                    for (i, rule) in rules.iter().enumerate() {
+                        let mut board = random_board(*width, *height, *grid, *topology, &mut random);
+                        for _ in 0..3 {
+                            let expected = reference_generation(&board, rule);
+                            let mut next = Board::with_shape(*width, *height, *grid, *topology);
+                            calculate_next_generation_parallel(&board, &mut next, rule, 1 + i);
+                            assert!(next == expected, "{} {} board of {}x{} under {} with {} threads", grid, topology, width, height, rule, 1 + i);
+                            board = next;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // The owner of the cell born at (x, y) from `parents`, calculated word-parallel.
+    fn born_owner(grid: Grid, rule: &Rule, parents: &[(usize, usize, PlayerNum)], x: usize, y: usize) -> Cell {
+        let mut board = Board::with_shape(12, 12, grid, Topology::BOUNDED);
+        for (px, py, owner) in parents.iter() {
+            board.set(*px, *py, Some(*owner));
+        }
+        let mut next = Board::with_shape(12, 12, grid, Topology::BOUNDED);
+        calculate_next_generation_parallel(&board, &mut next, rule, 1);
+        next.get(x, y)
+    }
+
+    #[test]
+    fn newborn_cells_go_to_the_owner_of_most_parents() {
+        use PlayerNum::{ONE, TWO};
+        assert_eq!(born_owner(Grid::SQUARE, &Rule::CONWAY, &[(4, 4, TWO), (6, 4, ONE), (5, 6, ONE)], 5, 5), Some(ONE));
+        assert_eq!(born_owner(Grid::SQUARE, &Rule::CONWAY, &[(4, 4, ONE), (6, 4, TWO), (5, 6, TWO)], 5, 5), Some(TWO));
+        // on ties the first parent clockwise from the upper right one wins: the right one before the left one
+        assert_eq!(born_owner(Grid::HEX, &Rule::HEXLIFE, &[(4, 4, ONE), (6, 4, TWO)], 5, 4), Some(TWO));
+        assert_eq!(born_owner(Grid::HEX, &Rule::HEXLIFE, &[(4, 4, TWO), (6, 4, ONE)], 5, 4), Some(ONE));
+        // and on an odd row, the lower right one before the left one
+        assert_eq!(born_owner(Grid::HEX, &Rule::HEXLIFE, &[(4, 5, ONE), (6, 6, TWO)], 5, 5), Some(TWO));
+    }
+
+    #[test]
+    fn every_thread_count_gives_the_same_generation() {
+        let mut random = Random(42);
+        let board = random_board(100, 97, Grid::SQUARE, Topology::CYLINDER, &mut random);
+        let expected = calculate_next_generation(&board, &Rule::CONWAY);
+        for threads in 2..8 {
+            let mut next = Board::with_shape(100, 97, Grid::SQUARE, Topology::CYLINDER);
+            calculate_next_generation_parallel(&board, &mut next, &Rule::CONWAY, threads);
+            assert!(next == expected, "{} threads", threads);
+        }
+    }
+}
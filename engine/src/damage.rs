@@ -0,0 +1,152 @@
+use crate::board::{Board, Cell};
+use crate::player::PlayerNum;
+use crate::topology::Topology;
+use crate::universe::Universe;
+
+// How alive cells reaching a player's edge of the board turn into damage.
+// A hit needs `run_length` vertically contiguous alive cells on the edge column.
+// When `scaled` is set, every `run_length` cells in such runs count as one more hit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HitModel {
+    pub run_length: usize,
+    pub scaled: bool
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    // The owner of most of the cells that reached the edge.
+    pub attacker: PlayerNum,
+    // How many cells on the edge were part of a long enough run.
+    pub cells: usize,
+    pub hits: usize
+}
+
+// The hits on player 1 and on player 2 in one generation.
+pub type Hits = (Option<Hit>, Option<Hit>);
+
+impl Default for HitModel {
+    fn default() -> Self {
+        HitModel {
+            run_length: 3,
+            scaled: false
+        }
+    }
+}
+
+// Checks both edges of the board, returning the hit on player 1 and the hit on player 2.
+// A torus has no edges, so nobody is ever hit on one.
+pub fn check_for_damage(board: &Board, model: &HitModel) -> Hits {
+    if !board.topology().has_goal_edges() {return (None, None)}
+
+    let last_x = board.width() - 1;
+    (check_edge(|y| board.get(0, y), board.height(), board.topology(), &PlayerNum::ONE, model),
+     check_edge(|y| board.get(last_x, y), board.height(), board.topology(), &PlayerNum::TWO, model))
+}
+
+// Like `check_for_damage`, for any universe.
+pub fn check_universe_for_damage(universe: &dyn Universe, model: &HitModel) -> Hits {
+    if !universe.topology().has_goal_edges() {return (None, None)}
+
+    let last_x = universe.width() - 1;
+    (check_edge(|y| universe.get(0, y), universe.height(), universe.topology(), &PlayerNum::ONE, model),
+     check_edge(|y| universe.get(last_x, y), universe.height(), universe.topology(), &PlayerNum::TWO, model))
+}
+
+// `edge` gives the cell at each row of the victim's edge column.
+// When the board wraps vertically, a run can continue from the bottom row into the top one.
+fn check_edge(edge: impl Fn(usize) -> Cell, height: usize, topology: Topology, victim: &PlayerNum, model: &HitModel) -> Option<Hit> {
+    // start right after a dead cell, so no run is split in two by the wrap
+    let first_row = if topology.wraps_vertically() {
+        match (0..height).find(|y| edge(*y).is_none()) {
+            Some(dead_row) => dead_row + 1,
+            None => 0
+        }
+    } else {
+        0
+    };
+
+    let (mut run, mut run_owned_by_victim) = (0, 0);
+    let (mut cells, mut owned_by_victim) = (0, 0);
+    // one extra iteration past the last row, to close a run that touches the bottom
+    for i in 0..=height {
+        let cell = if i < height {edge((first_row + i) % height)} else {None};
+        match cell {
+            Some(owner) => {
+                run += 1;
+                if owner == *victim {run_owned_by_victim += 1}
+            },
+            None => {
+                if run >= model.run_length {
+                    cells += run;
+                    owned_by_victim += run_owned_by_victim;
+                }
+                run = 0;
+                run_owned_by_victim = 0;
+            }
+        }
+    }
+
+    if cells == 0 {return None}
+
+    // on ties the hit is credited to the opponent
+    let attacker = if owned_by_victim * 2 > cells {*victim} else {victim.opponent()};
+    let hits = if model.scaled {cells / model.run_length} else {1};
+
+    Some(Hit {attacker, cells, hits})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::topology::Grid;
+
+    // A 6 x 12 board with the cells of `rows` alive on column `x`, owned by `owner`.
+    fn board_with_column(topology: Topology, x: usize, rows: &[usize], owner: PlayerNum) -> Board {
+        let mut board = Board::with_shape(6, 12, Grid::SQUARE, topology);
+        for y in rows.iter() {
+            board.set(x, *y, Some(owner));
+        }
+        board
+    }
+
+    #[test]
+    fn gaps_split_runs() {
+        let model = HitModel::default();
+        let board = board_with_column(Topology::BOUNDED, 0, &[0, 1, 3, 4, 6, 7], PlayerNum::TWO);
+        assert_eq!(check_for_damage(&board, &model), (None, None));
+
+        let board = board_with_column(Topology::BOUNDED, 0, &[0, 1, 3, 4, 6, 7, 8], PlayerNum::TWO);
+        assert_eq!(check_for_damage(&board, &model), (Some(Hit { attacker: PlayerNum::TWO, cells: 3, hits: 1 }), None));
+    }
+
+    #[test]
+    fn both_edges_are_hit_in_the_same_generation() {
+        let mut board = board_with_column(Topology::BOUNDED, 0, &[2, 3, 4], PlayerNum::TWO);
+        for y in 7..11 {
+            board.set(5, y, Some(PlayerNum::ONE));
+        }
+        assert_eq!(check_for_damage(&board, &HitModel::default()),
+                   (Some(Hit { attacker: PlayerNum::TWO, cells: 3, hits: 1 }), Some(Hit { attacker: PlayerNum::ONE, cells: 4, hits: 1 })));
+    }
+
+    #[test]
+    fn runs_cross_the_wrap_on_a_cylinder() {
+        let model = HitModel::default();
+        let board = board_with_column(Topology::BOUNDED, 5, &[10, 11, 0], PlayerNum::ONE);
+        assert_eq!(check_for_damage(&board, &model), (None, None));
+
+        let board = board_with_column(Topology::CYLINDER, 5, &[10, 11, 0], PlayerNum::ONE);
+        assert_eq!(check_for_damage(&board, &model), (None, Some(Hit { attacker: PlayerNum::ONE, cells: 3, hits: 1 })));
+
+        let board = board_with_column(Topology::TORUS, 5, &[10, 11, 0], PlayerNum::ONE);
+        assert_eq!(check_for_damage(&board, &model), (None, None));
+    }
+
+    #[test]
+    fn scaled_hits_count_every_run_length_cells() {
+        let board = board_with_column(Topology::BOUNDED, 0, &[0, 1, 2, 3, 4, 5, 6, 8, 9, 10], PlayerNum::TWO);
+        let scaled = HitModel { run_length: 3, scaled: true };
+        assert_eq!(check_for_damage(&board, &scaled).0, Some(Hit { attacker: PlayerNum::TWO, cells: 10, hits: 3 }));
+        assert_eq!(check_for_damage(&board, &HitModel::default()).0, Some(Hit { attacker: PlayerNum::TWO, cells: 10, hits: 1 }));
+    }
+}
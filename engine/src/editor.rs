@@ -0,0 +1,151 @@
+use crate::board::{calculate_next_generation, Board};
+use crate::pattern::Pattern;
+use crate::player::PlayerNum;
+use crate::point::Point2u;
+use crate::rules::Rule;
+use crate::topology::{Grid, Topology};
+
+// Size of the editor's canvas, in blocks.
+pub const EDITOR_WIDTH: usize = 40;
+pub const EDITOR_HEIGHT: usize = 26;
+
+// The longest name a pattern can be given in the editor.
+const MAX_NAME_LENGTH: usize = 32;
+
+// A canvas to paint patterns on, preview how they evolve and turn them into library patterns.
+#[derive(Debug, Clone)]
+pub struct Editor {
+    pub canvas: Board,
+    pub cursor: Point2u,
+    pub name: String,
+    // How many generations ahead the preview shows, 0 to show the canvas itself.
+    pub preview_generations: usize
+}
+
+impl Editor {
+    pub fn new(grid: Grid) -> Editor {
+        Editor {
+            canvas: Board::with_shape(EDITOR_WIDTH, EDITOR_HEIGHT, grid, Topology::BOUNDED),
+            cursor: pointu![EDITOR_WIDTH / 2, EDITOR_HEIGHT / 2],
+            name: String::new(),
+            preview_generations: 0
+        }
+    }
+
+    pub fn move_cursor(&mut self, dx: isize, dy: isize) {
+        self.cursor.x = (self.cursor.x as isize + dx).rem_euclid(EDITOR_WIDTH as isize) as usize;
+        self.cursor.y = (self.cursor.y as isize + dy).rem_euclid(EDITOR_HEIGHT as isize) as usize;
+    }
+
+    // Painting goes back to showing the canvas.
+    pub fn toggle_cell(&mut self) {
+        let cell = if self.canvas.is_alive(self.cursor.x, self.cursor.y) {None} else {Some(PlayerNum::ONE)};
+        self.canvas.set(self.cursor.x, self.cursor.y, cell);
+        self.preview_generations = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.canvas.clear();
+        self.preview_generations = 0;
+    }
+
+    // Adds a character to the name, if it can be part of a file name.
+    pub fn type_char(&mut self, c: char) {
+        if (c.is_ascii_alphanumeric() || c == '-' || c == '_') && self.name.len() < MAX_NAME_LENGTH {
+            self.name.push(c);
+        }
+    }
+
+    // The canvas after `preview_generations` generations under the rule.
+    pub fn preview(&self, rule: &Rule) -> Board {
+        let mut board = self.canvas.clone();
+        for _ in 0..self.preview_generations {
+            board = calculate_next_generation(&board, rule);
+        }
+        board
+    }
+
+    // The painted cells, cropped to the box around them, as a pattern made for the rule.
+    pub fn to_pattern(&self, rule: Rule) -> Result<Pattern, String> {
+        if self.name.is_empty() {
+            return Err("type a name for the pattern first".to_string())
+        }
+
+        let alive: Vec<Point2u> = (0..EDITOR_HEIGHT)
+            .flat_map(|y| (0..EDITOR_WIDTH).map(move |x| pointu![x, y]))
+            .filter(|p| self.canvas.is_alive(p.x, p.y))
+            .collect();
+        if alive.is_empty() {
+            return Err("paint some cells first".to_string())
+        }
+
+        let (left, right) = (alive.iter().map(|p| p.x).min().unwrap_or(0), alive.iter().map(|p| p.x).max().unwrap_or(0));
+        let (upper, lower) = (alive.iter().map(|p| p.y).min().unwrap_or(0), alive.iter().map(|p| p.y).max().unwrap_or(0));
+        Ok(Pattern::from_board(&self.name, &self.canvas, left, upper, right - left + 1, lower - upper + 1, rule))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An editor with the cells at `offsets` from the middle of the canvas painted.
+    fn painted(grid: Grid, offsets: &[(isize, isize)]) -> Editor {
+        let mut editor = Editor::new(grid);
+        for (dx, dy) in offsets.iter() {
+            editor.move_cursor(*dx, *dy);
+            editor.toggle_cell();
+            editor.move_cursor(-dx, -dy);
+        }
+        editor
+    }
+
+    const GLIDER: [(isize, isize); 5] = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+
+    #[test]
+    fn painted_cells_become_a_cropped_pattern() {
+        let mut editor = painted(Grid::SQUARE, &GLIDER);
+        assert!(editor.to_pattern(Rule::CONWAY).is_err());
+        editor.name = "glider".to_string();
+        // painting a cell twice leaves it empty
+        editor.move_cursor(5, 5);
+        editor.toggle_cell();
+        editor.toggle_cell();
+
+        let pattern = editor.to_pattern(Rule::CONWAY).unwrap();
+        assert_eq!((pattern.width, pattern.height), (3, 3));
+        let cells: Vec<(usize, usize)> = pattern.cells.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(cells, vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+
+        editor.clear();
+        assert!(editor.to_pattern(Rule::CONWAY).is_err());
+    }
+
+    #[test]
+    fn saved_patterns_read_back_the_same() {
+        for (grid, rule) in [(Grid::SQUARE, Rule::CONWAY), (Grid::HEX, Rule::HEXLIFE)].iter() {
+            // painted from an odd row, which hex patterns are moved up from to start on an even one
+            let mut editor = painted(*grid, &GLIDER);
+            assert!(!editor.cursor.y.is_multiple_of(2));
+            editor.name = "drawn".to_string();
+            let pattern = editor.to_pattern(*rule).unwrap();
+            assert_eq!(pattern.grid, Some(*grid));
+            assert_eq!(Pattern::parse("drawn.rle", &pattern.to_rle()), Ok(pattern));
+        }
+    }
+
+    #[test]
+    fn the_preview_runs_the_rule() {
+        let mut editor = painted(Grid::SQUARE, &GLIDER);
+        editor.preview_generations = 4;
+        let preview = editor.preview(&Rule::CONWAY);
+        let (x, y) = (EDITOR_WIDTH / 2, EDITOR_HEIGHT / 2);
+        for (dx, dy) in GLIDER.iter() {
+            assert!(preview.is_alive(x + 1 + *dx as usize, y + 1 + *dy as usize));
+        }
+        assert_eq!(preview.count_alive(), GLIDER.len());
+
+        editor.toggle_cell();
+        assert_eq!(editor.preview_generations, 0);
+    }
+}
@@ -0,0 +1,609 @@
+use crate::board::{calculate_next_generation_parallel, Board};
+use crate::damage::{check_for_damage, Hits};
+use crate::editor::Editor;
+use crate::history::{History, Snapshot};
+use crate::pattern::Pattern;
+use crate::player::{Player, PlayerNum};
+use crate::point::Point2u;
+use crate::settings::MatchSettings;
+use crate::stalemate::Stalemate;
+
+// The match advances in fixed ticks whatever the frame rate, and a generation is calculated every
+// `generation_ticks` ticks, so the same inputs at the same ticks always play out the same match.
+pub const TICKS_PER_SECOND: u32 = 60;
+pub const TICK_DURATION: f32 = 1.0 / TICKS_PER_SECOND as f32;
+// The default time between generations, in seconds.
+pub const GENERATION_CALCULATION_DELAY: f32 = 0.15;
+
+// The ticks per generation the speed controls go through, from the fastest to the slowest.
+const SPEED_STEPS: [u64; 9] = [1, 2, 3, 5, 9, 14, 22, 36, 60];
+
+// After a stall, at most this many ticks are caught up with in one update. The rest of the stall is skipped.
+const MAX_TICKS_PER_UPDATE: u32 = 30;
+
+// FROZEN shows the board with the clock stopped, so generations can be stepped through one at a time.
+// EDITOR shows the pattern editor, opened from the pause menu.
+#[derive(Debug,PartialEq)]
+pub enum GameState {
+    PLAYING,
+    FROZEN,
+    PAUSE_MENU,
+    EDITOR,
+    WINNER_SCREEN
+}
+
+// How a match ended.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Outcome {
+    WIN(PlayerNum),
+    DRAW
+}
+
+#[derive(Debug)]
+pub struct Game {
+    pub state: GameState,
+    // Elapsed seconds not yet turned into ticks.
+    accumulator: f32,
+    // Ticks played in the current match.
+    pub ticks: u64,
+    // Ticks between two generations, the current speed of the match.
+    pub generation_ticks: u64,
+    // Ticks since the last generation.
+    generation_timer: u64,
+    pub player1: Player,
+    pub player2: Player,
+    pub outcome: Option<Outcome>,
+    pub board: Board,
+    // The buffer the next generation is calculated into before being swapped with `board`.
+    next_board: Board,
+    // The most recent generations, to scrub back through while frozen.
+    pub history: History,
+    stalemate: Stalemate,
+    // The patterns players can stamp.
+    pub patterns: Vec<Pattern>,
+    pub editor: Editor,
+    // Read as the match goes, except for the board size and shape, the areas, the maximum HP and energy,
+    // the starting speed, `history_length` and `stalemate_window`, which only take effect on `reset`.
+    pub settings: MatchSettings
+}
+
+impl Game {
+    pub fn new(settings: MatchSettings) -> Game {
+        Game {
+            state: GameState::PAUSE_MENU,
+            accumulator: 0.0,
+            ticks: 0,
+            generation_ticks: settings.generation_ticks(),
+            generation_timer: 0,
+            player1:  Player::new(PlayerNum::ONE, &settings),
+            player2:  Player::new(PlayerNum::TWO, &settings),
+            outcome: None,
+            board: Board::with_shape(settings.board_width, settings.board_height, settings.grid, settings.topology),
+            next_board: Board::with_shape(settings.board_width, settings.board_height, settings.grid, settings.topology),
+            history: History::new(settings.history_length),
+            stalemate: Stalemate::new(settings.stalemate_window),
+            patterns: Pattern::built_in(),
+            editor: Editor::new(settings.grid),
+            settings
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.state = GameState::PLAYING;
+        self.accumulator = 0.0;
+        self.ticks = 0;
+        self.generation_ticks = self.settings.generation_ticks();
+        self.generation_timer = 0;
+        self.player1 = Player::new(PlayerNum::ONE, &self.settings);
+        self.player2 = Player::new(PlayerNum::TWO, &self.settings);
+        self.outcome = None;
+        self.board = Board::with_shape(self.settings.board_width, self.settings.board_height, self.settings.grid, self.settings.topology);
+        self.next_board = Board::with_shape(self.settings.board_width, self.settings.board_height, self.settings.grid, self.settings.topology);
+        self.history = History::new(self.settings.history_length);
+        self.stalemate = Stalemate::new(self.settings.stalemate_window);
+    }
+
+    // Advances the game clock by `elapsed` seconds, running every tick that became due.
+    // The time left over that does not make a whole tick is kept for the next update.
+    pub fn update(&mut self, elapsed: f32) {
+        if self.state != GameState::PLAYING {return}
+
+        self.accumulator = (self.accumulator + elapsed).min(MAX_TICKS_PER_UPDATE as f32 * TICK_DURATION);
+        while self.accumulator >= TICK_DURATION && self.state == GameState::PLAYING {
+            self.accumulator -= TICK_DURATION;
+            self.tick();
+        }
+    }
+
+    // Advances the match by one tick, calculating the next generation when it is due.
+    pub fn tick(&mut self) {
+        if self.state != GameState::PLAYING {return}
+
+        self.ticks += 1;
+        let energy = self.settings.energy_per_second * TICK_DURATION;
+        self.player1.regenerate_energy(energy);
+        self.player2.regenerate_energy(energy);
+        self.player1.cool_down(TICK_DURATION);
+        self.player2.cool_down(TICK_DURATION);
+        self.generation_timer += 1;
+        if self.generation_timer >= self.generation_ticks {
+            self.generation_timer = 0;
+            self.step_generation();
+        }
+
+        if self.state == GameState::PLAYING && self.time_left() == Some(0.0) {
+            self.finish(self.leading_player().map_or(Outcome::DRAW, Outcome::WIN));
+        }
+    }
+
+    // Calculates the next generation and the damage dealt on the way, without moving the clock.
+    pub fn step_generation(&mut self) {
+        let snapshot = self.snapshot();
+        self.history.record(snapshot);
+
+        let damage_in_each_player = check_for_damage(&self.board, &self.settings.hit_model);
+        let hit = damage_in_each_player.0.is_some() || damage_in_each_player.1.is_some();
+        calculate_next_generation_parallel(&self.board, &mut self.next_board, &self.settings.rule, self.settings.thread_count());
+        std::mem::swap(&mut self.board, &mut self.next_board);
+        make_damage_calculations(self, damage_in_each_player);
+
+        // an empty board is waiting for the players rather than stalled, and nobody can be hit on a torus anyway
+        if !self.board.topology().has_goal_edges() {return}
+        if self.board.count_alive() == 0 {
+            self.stalemate.clear();
+            return
+        }
+        if self.outcome.is_none() && self.stalemate.record(&self.board, hit) {
+            self.finish(self.tiebreak_leader().map_or(Outcome::DRAW, Outcome::WIN));
+        }
+    }
+
+    // Goes back one generation in the history. Only while frozen, the match resumes from there when unfrozen.
+    pub fn rewind(&mut self) {
+        if self.state != GameState::FROZEN {return}
+
+        let present = self.snapshot();
+        if let Some(snapshot) = self.history.back(|| present).cloned() {
+            self.restore(&snapshot);
+        }
+    }
+
+    // Goes forward one generation in the history, towards the generation the match was frozen at.
+    pub fn fast_forward(&mut self) {
+        if self.state != GameState::FROZEN {return}
+
+        if let Some(snapshot) = self.history.forward().cloned() {
+            self.restore(&snapshot);
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            board: self.board.clone(),
+            ticks: self.ticks,
+            players: [self.player1.clone(), self.player2.clone()]
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.board.clone_from(&snapshot.board);
+        self.ticks = snapshot.ticks;
+        self.generation_timer = 0;
+        self.stalemate.clear();
+        // the cursors, the selection tools and the saved selections stay as they are
+        for (player, saved) in [&mut self.player1, &mut self.player2].iter_mut().zip(snapshot.players.iter()) {
+            let (hovering_square, selection_anchor, selection_tool) = (player.hovering_square, player.selection_anchor, player.selection_tool);
+            let dragging_selection = player.dragging_selection;
+            let selection_slots = std::mem::take(&mut player.selection_slots);
+            player.clone_from(saved);
+            player.hovering_square = hovering_square;
+            player.selection_anchor = selection_anchor;
+            player.selection_tool = selection_tool;
+            player.dragging_selection = dragging_selection;
+            player.selection_slots = selection_slots;
+        }
+    }
+
+    pub fn speed_up(&mut self) {
+        if let Some(ticks) = SPEED_STEPS.iter().rev().find(|ticks| **ticks < self.generation_ticks) {
+            self.generation_ticks = *ticks;
+        }
+    }
+
+    pub fn slow_down(&mut self) {
+        if let Some(ticks) = SPEED_STEPS.iter().find(|ticks| **ticks > self.generation_ticks) {
+            self.generation_ticks = *ticks;
+        }
+    }
+
+    // Back to the speed the match started with.
+    pub fn reset_speed(&mut self) {
+        self.generation_ticks = self.settings.generation_ticks();
+    }
+
+    // How many times faster than the speed the match started with generations are calculated.
+    pub fn speed(&self) -> f32 {
+        self.settings.generation_ticks() as f32 / self.generation_ticks as f32
+    }
+
+    // Seconds played in the current match.
+    pub fn match_time(&self) -> f32 {
+        self.ticks as f32 / TICKS_PER_SECOND as f32
+    }
+
+    // Seconds left in a timed match.
+    pub fn time_left(&self) -> Option<f32> {
+        self.settings.time_limit.map(|limit| (limit - self.match_time()).max(0.0))
+    }
+
+    // The player with more HP, if any.
+    pub fn leading_player(&self) -> Option<PlayerNum> {
+        match self.player1.hp.cmp(&self.player2.hp) {
+            std::cmp::Ordering::Greater => Some(PlayerNum::ONE),
+            std::cmp::Ordering::Less => Some(PlayerNum::TWO),
+            std::cmp::Ordering::Equal => None
+        }
+    }
+
+    // The player with more HP, or with more alive cells on equal HP, if any.
+    pub fn tiebreak_leader(&self) -> Option<PlayerNum> {
+        self.leading_player().or_else(|| {
+            match self.board.count_owned(&PlayerNum::ONE).cmp(&self.board.count_owned(&PlayerNum::TWO)) {
+                std::cmp::Ordering::Greater => Some(PlayerNum::ONE),
+                std::cmp::Ordering::Less => Some(PlayerNum::TWO),
+                std::cmp::Ordering::Equal => None
+            }
+        })
+    }
+
+    pub fn finish(&mut self, outcome: Outcome) {
+        self.outcome = Some(outcome);
+        self.state = GameState::WINNER_SCREEN;
+    }
+
+    pub fn player(&self, player_num: &PlayerNum) -> &Player {
+        match player_num {
+            PlayerNum::ONE => &self.player1,
+            PlayerNum::TWO => &self.player2
+        }
+    }
+
+    pub fn player_mut(&mut self, player_num: &PlayerNum) -> &mut Player {
+        match player_num {
+            PlayerNum::ONE => &mut self.player1,
+            PlayerNum::TWO => &mut self.player2
+        }
+    }
+
+    // Opens the pattern editor from the pause menu. The canvas is kept between visits unless the grid changed.
+    pub fn open_editor(&mut self) {
+        if self.state != GameState::PAUSE_MENU {return}
+
+        if self.editor.canvas.grid() != self.settings.grid {
+            self.editor = Editor::new(self.settings.grid);
+        }
+        self.state = GameState::EDITOR;
+    }
+
+    pub fn close_editor(&mut self) {
+        if self.state == GameState::EDITOR {
+            self.state = GameState::PAUSE_MENU;
+        }
+    }
+
+    // Chooses the pattern `step` places further along the library, wrapping around.
+    pub fn cycle_pattern(&mut self, player_num: &PlayerNum, step: isize) {
+        let count = self.patterns.len() as isize;
+        if count == 0 {return}
+
+        let player = self.player_mut(player_num);
+        player.pattern = (player.pattern as isize + step).rem_euclid(count) as usize;
+    }
+
+    // The player's pattern if the library has it.
+    pub fn player_pattern(&self, player_num: &PlayerNum) -> Option<&Pattern> {
+        self.patterns.get(self.player(player_num).pattern)
+    }
+
+    // The squares the player's pattern covers when stamped: turned by their orientation,
+    // centered on their hovering square and clipped to their area.
+    pub fn stamp_squares(&self, player_num: &PlayerNum) -> Vec<Point2u> {
+        let player = self.player(player_num);
+        let pattern = match self.player_pattern(player_num) {
+            Some(pattern) => pattern,
+            None => return Vec::new()
+        };
+
+        let (cells, width, height) = pattern.oriented(player.orientation, self.board.grid());
+        let (left, right, upper, lower) = player.area_bounds();
+        let origin = (player.hovering_square.x as isize - (width / 2) as isize, player.hovering_square.y as isize - (height / 2) as isize);
+        // patterns start on an even row, moved as a whole so they keep their shape on hex grids
+        cells.iter()
+            .map(|p| self.board.grid().translate((p.x as isize, p.y as isize), origin))
+            .filter(|(x, y)| *x >= left as isize && *x <= right as isize && *y >= upper as isize && *y <= lower as isize)
+            .map(|(x, y)| pointu![x as usize, y as usize])
+            .collect()
+    }
+
+    // Adds the squares of the player's pattern to their selection.
+    pub fn stamp_pattern(&mut self, player_num: &PlayerNum) {
+        let squares = self.stamp_squares(player_num);
+        let player = self.player_mut(player_num);
+        for square in squares {
+            if !player.selected_squares.contains(&square) {
+                player.selected_squares.push(square);
+            }
+        }
+    }
+
+    // Places every empty square the player has selected on the board, owned by the player, paying `cell_cost` energy for each.
+    // Committing starts the commit cooldown. When the player is still cooling down or cannot afford the squares
+    // nothing is placed, and the error says why.
+    pub fn commit_selected_squares(&mut self, player_num: &PlayerNum) -> Result<(), String> {
+        let player = match player_num {
+            PlayerNum::ONE => &mut self.player1,
+            PlayerNum::TWO => &mut self.player2
+        };
+        if player.selected_squares.is_empty() {return Ok(())}
+        if player.movement_cooldown_time > 0.0 {
+            return Err(format!("cooling down: {:.1}s left", player.movement_cooldown_time))
+        }
+        // squares that are already alive keep whoever built them
+        let board = &self.board;
+        let empty_squares: Vec<Point2u> = player.selected_squares.iter().copied().filter(|p| board.get(p.x, p.y).is_none()).collect();
+        let cost = (empty_squares.len() * self.settings.cell_cost) as f32;
+        if cost > player.energy {
+            return Err(format!("not enough energy: {} needed, {} left", cost, player.energy.floor()))
+        }
+
+        player.energy -= cost;
+        player.movement_cooldown_time = self.settings.commit_cooldown;
+        for p in empty_squares.iter() {
+            self.board.set(p.x, p.y, Some(*player_num));
+        }
+        player.stats.cells_placed += empty_squares.len();
+        player.selected_squares.clear();
+        Ok(())
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new(MatchSettings::default())
+    }
+}
+
+// `players_damage` holds the hits on player 1 and player 2. Both are applied before anyone is checked for death.
+pub fn make_damage_calculations(game: &mut Game, players_damage: Hits) {
+    for (victim, hit) in [(PlayerNum::ONE, players_damage.0), (PlayerNum::TWO, players_damage.1)].iter() {
+        if let Some(hit) = hit {
+            let amount = hit.hits * game.settings.damage_per_hit;
+            game.player_mut(victim).take_damage(amount);
+            let stats = &mut game.player_mut(&hit.attacker).stats;
+            if hit.attacker == *victim {
+                stats.own_hits += hit.hits;
+            } else {
+                stats.hits_dealt += hit.hits;
+            }
+        }
+    }
+
+    match (game.player1.is_dead(), game.player2.is_dead()) {
+        (true, true)  => game.finish(Outcome::DRAW),
+        (true, false) => game.finish(Outcome::WIN(PlayerNum::TWO)),
+        (false, true) => game.finish(Outcome::WIN(PlayerNum::ONE)),
+        (false, false) => ()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A match that has started, with a blinker of player 1 in the middle of the board.
+    fn playing_game(settings: MatchSettings) -> Game {
+        let mut game = Game::new(settings);
+        game.reset();
+        for x in 24..27 {
+            game.board.set(x, 16, Some(PlayerNum::ONE));
+        }
+        game
+    }
+
+    #[test]
+    fn rewinding_is_off_in_a_normal_match() {
+        let mut game = playing_game(MatchSettings::default());
+        for _ in 0..3 {
+            game.step_generation();
+        }
+        game.state = GameState::FROZEN;
+        let board = game.board.clone();
+        game.rewind();
+        assert_eq!(game.board, board);
+        assert_eq!(game.history.rewound(), 0);
+
+        let mut game = playing_game(MatchSettings { history_length: 10, ..MatchSettings::default() });
+        let board = game.board.clone();
+        game.step_generation();
+        game.state = GameState::FROZEN;
+        game.rewind();
+        assert_eq!(game.board, board);
+    }
+
+    #[test]
+    fn only_empty_squares_cost_energy() {
+        let mut game = playing_game(MatchSettings::default());
+        game.board.set(6, 10, Some(PlayerNum::TWO));
+        game.player1.selected_squares = vec![pointu![5, 10], pointu![6, 10], pointu![7, 10]];
+        assert!(game.commit_selected_squares(&PlayerNum::ONE).is_ok());
+        assert_eq!(game.player1.energy, 38.0);
+        assert_eq!(game.player1.stats.cells_placed, 2);
+        assert_eq!(game.board.get(5, 10), Some(PlayerNum::ONE));
+        assert_eq!(game.board.get(6, 10), Some(PlayerNum::TWO));
+        assert_eq!(game.board.get(7, 10), Some(PlayerNum::ONE));
+    }
+
+    #[test]
+    fn unaffordable_commits_change_nothing() {
+        let mut game = playing_game(MatchSettings::default());
+        game.player1.energy = 2.0;
+        game.player1.selected_squares = vec![pointu![5, 10], pointu![6, 10], pointu![7, 10]];
+        let board = game.board.clone();
+        let error = game.commit_selected_squares(&PlayerNum::ONE).unwrap_err();
+        assert!(error.starts_with("not enough energy"), "{}", error);
+        assert_eq!(game.board, board);
+        assert_eq!(game.player1.energy, 2.0);
+        assert_eq!(game.player1.selected_squares.len(), 3);
+    }
+
+    #[test]
+    fn energy_regenerates_up_to_the_maximum() {
+        let mut game = playing_game(MatchSettings::default());
+        game.player1.energy = 0.0;
+        for _ in 0..TICKS_PER_SECOND {
+            game.tick();
+        }
+        assert!((game.player1.energy - game.settings.energy_per_second).abs() < 0.001);
+
+        game.player1.energy = 39.5;
+        for _ in 0..TICKS_PER_SECOND {
+            game.tick();
+        }
+        assert_eq!(game.player1.energy, 40.0);
+        assert_eq!(game.player2.energy, 40.0);
+    }
+
+    #[test]
+    fn commits_wait_for_the_cooldown() {
+        let mut game = playing_game(MatchSettings::default());
+        game.player1.selected_squares = vec![pointu![5, 10]];
+        assert!(game.commit_selected_squares(&PlayerNum::ONE).is_ok());
+
+        game.player1.selected_squares = vec![pointu![10, 20]];
+        let board = game.board.clone();
+        let error = game.commit_selected_squares(&PlayerNum::ONE).unwrap_err();
+        assert!(error.starts_with("cooling down"), "{}", error);
+        assert_eq!(game.board, board);
+
+        // a little more than `commit_cooldown` in ticks, for the rounding of the tick duration
+        for _ in 0..(game.settings.commit_cooldown * TICKS_PER_SECOND as f32) as u32 + 1 {
+            game.tick();
+        }
+        assert!(game.commit_selected_squares(&PlayerNum::ONE).is_ok());
+        assert_eq!(game.board.get(10, 20), Some(PlayerNum::ONE));
+    }
+
+    // Plays the same match for `seconds`, with updates of the elapsed times in `frames` over and over.
+    fn play(frames: &[f32], seconds: f32) -> Game {
+        let mut game = playing_game(MatchSettings::default());
+        // runs on both edges, hitting both players from the first generation
+        for y in 5..10 {
+            game.board.set(0, y, Some(PlayerNum::TWO));
+            game.board.set(50, y + 10, Some(PlayerNum::ONE));
+        }
+        game.player1.selected_squares = vec![pointu![10, 10], pointu![11, 10], pointu![11, 9], pointu![11, 11], pointu![12, 9]];
+        game.commit_selected_squares(&PlayerNum::ONE).unwrap();
+
+        let mut elapsed = 0.0;
+        for frame in frames.iter().cycle() {
+            let frame = frame.min(seconds - elapsed);
+            if frame <= 0.0 {break}
+            game.update(frame);
+            elapsed += frame;
+        }
+        game
+    }
+
+    #[test]
+    fn frame_times_do_not_change_the_match() {
+        // long enough for a player to die, so the match stops at the same tick for both
+        let steady = play(&[1.0 / 60.0], 8.0);
+        let uneven = play(&[0.005, 0.03, 0.0117], 8.0);
+        assert!(steady.outcome.is_some());
+        assert_eq!(uneven.outcome, steady.outcome);
+        assert_eq!(uneven.ticks, steady.ticks);
+        assert_eq!(uneven.board, steady.board);
+        assert_eq!((uneven.player1.hp, uneven.player2.hp), (steady.player1.hp, steady.player2.hp));
+    }
+
+    #[test]
+    fn time_short_of_a_tick_is_kept_for_the_next_update() {
+        let mut game = playing_game(MatchSettings::default());
+        game.update(TICK_DURATION * 0.6);
+        assert_eq!(game.ticks, 0);
+        game.update(TICK_DURATION * 0.6);
+        assert_eq!(game.ticks, 1);
+        game.update(TICK_DURATION * 1.7);
+        assert_eq!(game.ticks, 2);
+        game.update(TICK_DURATION * 0.2);
+        assert_eq!(game.ticks, 3);
+    }
+
+    #[test]
+    fn speed_stays_within_the_steps() {
+        let mut game = playing_game(MatchSettings::default());
+        assert_eq!(game.generation_ticks, 9);
+        for _ in 0..SPEED_STEPS.len() + 2 {
+            game.speed_up();
+            assert!(SPEED_STEPS.contains(&game.generation_ticks));
+        }
+        assert_eq!(game.generation_ticks, SPEED_STEPS[0]);
+        assert_eq!(game.speed(), 9.0);
+
+        for _ in 0..SPEED_STEPS.len() + 2 {
+            game.slow_down();
+            assert!(SPEED_STEPS.contains(&game.generation_ticks));
+        }
+        assert_eq!(game.generation_ticks, SPEED_STEPS[SPEED_STEPS.len() - 1]);
+
+        game.reset_speed();
+        assert_eq!(game.generation_ticks, 9);
+        assert_eq!(game.speed(), 1.0);
+    }
+
+    #[test]
+    fn generations_come_every_generation_ticks() {
+        for speed_ups in 0..3 {
+            let mut game = playing_game(MatchSettings::default());
+            for _ in 0..speed_ups {
+                game.speed_up();
+            }
+            // the blinker turns at every generation
+            let board = game.board.clone();
+            for _ in 1..game.generation_ticks {
+                game.tick();
+            }
+            assert_eq!(game.board, board);
+            game.tick();
+            assert_ne!(game.board, board);
+        }
+    }
+
+    #[test]
+    fn killing_each_other_in_one_generation_is_a_draw() {
+        let mut game = playing_game(MatchSettings::default());
+        game.player1.hp = 10;
+        game.player2.hp = 10;
+        for y in 5..10 {
+            game.board.set(0, y, Some(PlayerNum::TWO));
+            game.board.set(50, y, Some(PlayerNum::ONE));
+        }
+        game.step_generation();
+        assert_eq!(game.outcome, Some(Outcome::DRAW));
+        assert_eq!(game.state, GameState::WINNER_SCREEN);
+    }
+
+    #[test]
+    fn timed_matches_go_to_the_player_with_more_hp() {
+        for (hp2, outcome) in [(30, Outcome::WIN(PlayerNum::ONE)), (50, Outcome::DRAW)].iter() {
+            let mut game = playing_game(MatchSettings { time_limit: Some(1.0), ..MatchSettings::default() });
+            game.player2.hp = *hp2;
+            for _ in 0..TICKS_PER_SECOND - 1 {
+                game.tick();
+            }
+            assert_eq!(game.outcome, None);
+            game.tick();
+            assert_eq!(game.outcome, Some(*outcome));
+        }
+    }
+}
@@ -0,0 +1,473 @@
+use std::collections::HashMap;
+
+use crate::board::{majority_owner, Board, Cell, NEIGHBOUR_OFFSETS};
+use crate::player::PlayerNum;
+use crate::rules::Rule;
+use crate::topology::{Grid, Topology};
+use crate::universe::Universe;
+
+type NodeId = u32;
+
+// The leaves of the quadtree, one per cell state. Their ids are their states.
+// The board is surrounded by walls, which are never alive and never change,
+// so the unbounded quadtree evolves exactly like the bounded board.
+const DEAD: NodeId = 0;
+const PLAYER_1: NodeId = 1;
+const PLAYER_2: NodeId = 2;
+const WALL: NodeId = 3;
+
+// Once there are this many nodes after a jump, the ones no longer reachable from the root are dropped.
+const MAX_NODES: usize = 1 << 22;
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    level: u8,
+    // nw, ne, sw, se
+    children: [NodeId; 4],
+    // How many alive cells are under this node.
+    alive: u64
+}
+
+// A HashLife universe: the board is a quadtree of hash-consed nodes, and the result of advancing
+// every node is memoized, so repetitive boards can jump thousands of generations ahead at once.
+// Cell (x, y) of the board is cell (x, y) of the root, everything else in the root is wall.
+// The walls make every edge a dead border, so only bounded boards of square cells can be modelled.
+#[derive(Debug, Clone)]
+pub struct HashLife {
+    width: usize,
+    height: usize,
+    rule: Rule,
+    nodes: Vec<Node>,
+    ids: HashMap<[NodeId; 4], NodeId>,
+    // The center of a node advanced by 2^step generations, by (node, step).
+    results: HashMap<(NodeId, u8), NodeId>,
+    // Nodes made only of walls and only of dead cells, by level.
+    walls: Vec<NodeId>,
+    deads: Vec<NodeId>,
+    root: NodeId,
+    generation: u64
+}
+
+impl HashLife {
+    pub fn new(width: usize, height: usize, rule: Rule) -> HashLife {
+        HashLife::build_from(&Board::new(width, height), rule)
+    }
+
+    // Only bounded boards of square cells can be modelled, any other board is an error.
+    pub fn from_board(board: &Board, rule: Rule) -> Result<HashLife, String> {
+        if board.topology() != Topology::BOUNDED {
+            return Err(format!("HashLife only supports bounded boards, not a {}", board.topology()))
+        }
+        if board.grid() != Grid::SQUARE {
+            return Err(format!("HashLife only supports square grids, not a {} grid", board.grid()))
+        }
+        Ok(HashLife::build_from(board, rule))
+    }
+
+    fn build_from(board: &Board, rule: Rule) -> HashLife {
+        let mut universe = HashLife {
+            width: board.width(),
+            height: board.height(),
+            rule,
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+            results: HashMap::new(),
+            walls: Vec::new(),
+            deads: Vec::new(),
+            root: DEAD,
+            generation: 0
+        };
+        universe.add_leaves();
+
+        let mut level = 2;
+        while (1 << level) < board.width().max(board.height()) {
+            level += 1;
+        }
+        universe.root = universe.build(board, level, 0, 0);
+        universe
+    }
+
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    pub fn count_alive(&self) -> u64 {
+        self.nodes[self.root as usize].alive
+    }
+
+    fn add_leaves(&mut self) {
+        for state in [DEAD, PLAYER_1, PLAYER_2, WALL].iter() {
+            let alive = if *state == PLAYER_1 || *state == PLAYER_2 {1} else {0};
+            self.nodes.push(Node {level: 0, children: [*state; 4], alive});
+        }
+        self.walls = vec![WALL];
+        self.deads = vec![DEAD];
+    }
+
+    fn build(&mut self, board: &Board, level: u8, x0: usize, y0: usize) -> NodeId {
+        let size = 1 << level;
+        if x0 >= self.width || y0 >= self.height {
+            return self.wall(level)
+        }
+        if level == 0 {
+            return cell_to_leaf(board.get(x0, y0))
+        }
+        if x0 + size <= self.width && y0 + size <= self.height && board.is_region_dead(x0, y0, size, size) {
+            return self.dead(level)
+        }
+
+        let half = size / 2;
+        let children = [
+            self.build(board, level - 1, x0, y0),
+            self.build(board, level - 1, x0 + half, y0),
+            self.build(board, level - 1, x0, y0 + half),
+            self.build(board, level - 1, x0 + half, y0 + half)
+        ];
+        self.node(children)
+    }
+
+    // The node with these children, created if it does not exist yet.
+    fn node(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(id) = self.ids.get(&children) {
+            return *id
+        }
+
+        let level = self.nodes[children[0] as usize].level + 1;
+        let alive = children.iter().map(|child| self.nodes[*child as usize].alive).sum();
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {level, children, alive});
+        self.ids.insert(children, id);
+        id
+    }
+
+    fn wall(&mut self, level: u8) -> NodeId {
+        while self.walls.len() <= level as usize {
+            let below = *self.walls.last().unwrap();
+            let node = self.node([below; 4]);
+            self.walls.push(node);
+        }
+        self.walls[level as usize]
+    }
+
+    fn dead(&mut self, level: u8) -> NodeId {
+        while self.deads.len() <= level as usize {
+            let below = *self.deads.last().unwrap();
+            let node = self.node([below; 4]);
+            self.deads.push(node);
+        }
+        self.deads[level as usize]
+    }
+
+    fn children(&self, id: NodeId) -> [NodeId; 4] {
+        self.nodes[id as usize].children
+    }
+
+    // The node of one level lower at the center of this one.
+    fn center(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(id);
+        self.node([self.children(nw)[3], self.children(ne)[2], self.children(sw)[1], self.children(se)[0]])
+    }
+
+    // The center of the node, one level lower, advanced 2^step generations. `step` is at most the level - 2.
+    fn advance_node(&mut self, id: NodeId, step: u8) -> NodeId {
+        if let Some(result) = self.results.get(&(id, step)) {
+            return *result
+        }
+
+        let node = self.nodes[id as usize];
+        let result = if node.alive == 0 {
+            // without alive cells nothing can ever be born, walls and dead cells stay as they are
+            self.center(id)
+        } else if node.level == 2 {
+            self.advance_leaves(id)
+        } else {
+            let [a, b, c, d] = node.children;
+            let ([_, a_ne, a_sw, a_se], [b_nw, _, b_sw, b_se]) = (self.children(a), self.children(b));
+            let ([c_nw, c_ne, _, c_se], [d_nw, d_ne, d_sw, _]) = (self.children(c), self.children(d));
+            let overlapping = [
+                a, self.node([a_ne, b_nw, a_se, b_sw]), b,
+                self.node([a_sw, a_se, c_nw, c_ne]), self.node([a_se, b_sw, c_ne, d_nw]), self.node([b_sw, b_se, d_nw, d_ne]),
+                c, self.node([c_ne, d_nw, c_se, d_sw]), d
+            ];
+
+            // at full speed both halves of the jump advance, otherwise only the second one does
+            let full_speed = step == node.level - 2;
+            let inner_step = if full_speed {step - 1} else {step};
+            let mut r = [DEAD; 9];
+            for (i, id) in overlapping.iter().enumerate() {
+                r[i] = if full_speed {self.advance_node(*id, inner_step)} else {self.center(*id)};
+            }
+
+            let quadrants = [
+                self.node([r[0], r[1], r[3], r[4]]), self.node([r[1], r[2], r[4], r[5]]),
+                self.node([r[3], r[4], r[6], r[7]]), self.node([r[4], r[5], r[7], r[8]])
+            ];
+            let mut advanced = [DEAD; 4];
+            for (i, id) in quadrants.iter().enumerate() {
+                advanced[i] = self.advance_node(*id, inner_step);
+            }
+            self.node(advanced)
+        };
+
+        self.results.insert((id, step), result);
+        result
+    }
+
+    // Advances the 2x2 center of a 4x4 node by one generation, cell by cell.
+    fn advance_leaves(&mut self, id: NodeId) -> NodeId {
+        let mut grid = [[DEAD; 4]; 4];
+        for (quadrant, child) in self.children(id).iter().enumerate() {
+            let (qx, qy) = (quadrant % 2 * 2, quadrant / 2 * 2);
+            for (i, leaf) in self.children(*child).iter().enumerate() {
+                grid[qy + i / 2][qx + i % 2] = *leaf;
+            }
+        }
+
+        let mut result = [DEAD; 4];
+        for (i, leaf) in result.iter_mut().enumerate() {
+            *leaf = next_leaf(&grid, 1 + i % 2, 1 + i / 2, &self.rule);
+        }
+        self.node(result)
+    }
+
+    // Advances the whole board by 2^step generations.
+    fn jump(&mut self, step: u8) {
+        // surround the root with walls, so that its center, which comes back, is the root itself
+        let [a, b, c, d] = self.children(self.root);
+        let wall = self.wall(self.nodes[self.root as usize].level - 1);
+        let padded = [
+            self.node([wall, wall, wall, a]), self.node([wall, wall, b, wall]),
+            self.node([wall, c, wall, wall]), self.node([d, wall, wall, wall])
+        ];
+        let padded = self.node(padded);
+
+        self.root = self.advance_node(padded, step);
+        self.generation += 1 << step;
+
+        if self.nodes.len() > MAX_NODES {
+            self.collect_garbage();
+        }
+    }
+
+    // Rebuilds the node arena with only the nodes reachable from the root.
+    fn collect_garbage(&mut self) {
+        let old_nodes = std::mem::take(&mut self.nodes);
+        self.ids.clear();
+        self.results.clear();
+        self.add_leaves();
+
+        let mut copied = HashMap::new();
+        self.root = self.copy_node(&old_nodes, self.root, &mut copied);
+    }
+
+    fn copy_node(&mut self, old_nodes: &[Node], id: NodeId, copied: &mut HashMap<NodeId, NodeId>) -> NodeId {
+        if old_nodes[id as usize].level == 0 {
+            return id
+        }
+        if let Some(new_id) = copied.get(&id) {
+            return *new_id
+        }
+
+        let mut children = old_nodes[id as usize].children;
+        for child in children.iter_mut() {
+            *child = self.copy_node(old_nodes, *child, copied);
+        }
+        let new_id = self.node(children);
+        copied.insert(id, new_id);
+        new_id
+    }
+
+    fn set_in(&mut self, id: NodeId, x: usize, y: usize, leaf: NodeId) -> NodeId {
+        let level = self.nodes[id as usize].level;
+        if level == 0 {
+            return leaf
+        }
+
+        let half = 1 << (level - 1);
+        let quadrant = (y / half) * 2 + x / half;
+        let mut children = self.children(id);
+        children[quadrant] = self.set_in(children[quadrant], x % half, y % half, leaf);
+        self.node(children)
+    }
+
+    fn copy_to_board(&self, id: NodeId, x0: usize, y0: usize, board: &mut Board) {
+        let node = self.nodes[id as usize];
+        if node.alive == 0 {return}
+
+        if node.level == 0 {
+            board.set(x0, y0, leaf_to_cell(id));
+            return
+        }
+
+        let half = 1 << (node.level - 1);
+        for (quadrant, child) in node.children.iter().enumerate() {
+            self.copy_to_board(*child, x0 + quadrant % 2 * half, y0 + quadrant / 2 * half, board);
+        }
+    }
+}
+
+impl Universe for HashLife {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn topology(&self) -> Topology {
+        Topology::BOUNDED
+    }
+
+    fn get(&self, x: usize, y: usize) -> Cell {
+        assert!(x < self.width && y < self.height, "cell out of the board");
+
+        let mut id = self.root;
+        let (mut x, mut y) = (x, y);
+        loop {
+            let node = self.nodes[id as usize];
+            if node.level == 0 {
+                return leaf_to_cell(id)
+            }
+            if node.alive == 0 {
+                return None
+            }
+
+            let half = 1 << (node.level - 1);
+            id = node.children[(y / half) * 2 + x / half];
+            x %= half;
+            y %= half;
+        }
+    }
+
+    fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        assert!(x < self.width && y < self.height, "cell out of the board");
+        self.root = self.set_in(self.root, x, y, cell_to_leaf(cell));
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn step(&mut self) {
+        self.jump(0);
+    }
+
+    // Jumps in the biggest powers of two the root allows.
+    fn advance(&mut self, generations: u64) {
+        let max_step = self.nodes[self.root as usize].level as u32 - 1;
+        let mut left = generations;
+        while left > 0 {
+            let step = (63 - left.leading_zeros()).min(max_step);
+            self.jump(step as u8);
+            left -= 1 << step;
+        }
+    }
+
+    fn to_board(&self) -> Board {
+        let mut board = Board::new(self.width, self.height);
+        self.copy_to_board(self.root, 0, 0, &mut board);
+        board
+    }
+}
+
+fn cell_to_leaf(cell: Cell) -> NodeId {
+    match cell {
+        None => DEAD,
+        Some(PlayerNum::ONE) => PLAYER_1,
+        Some(PlayerNum::TWO) => PLAYER_2
+    }
+}
+
+fn leaf_to_cell(leaf: NodeId) -> Cell {
+    match leaf {
+        PLAYER_1 => Some(PlayerNum::ONE),
+        PLAYER_2 => Some(PlayerNum::TWO),
+        _ => None
+    }
+}
+
+// The next state of the cell at (x, y) of the grid, following the same rules as `calculate_next_generation`.
+fn next_leaf(grid: &[[NodeId; 4]; 4], x: usize, y: usize, rule: &Rule) -> NodeId {
+    let leaf = grid[y][x];
+    if leaf == WALL {
+        return WALL
+    }
+
+    let neighbours = NEIGHBOUR_OFFSETS.iter()
+        .map(|(dx, dy)| leaf_to_cell(grid[(y as isize + dy) as usize][(x as isize + dx) as usize]));
+    let alive_neighbours = neighbours.clone().filter(Option::is_some).count();
+    let alive = leaf != DEAD;
+    if !rule.next_state(alive, alive_neighbours) {
+        DEAD
+    } else if alive {
+        leaf
+    } else {
+        cell_to_leaf(majority_owner(neighbours))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{random_board, Random};
+    use crate::damage::{check_for_damage, HitModel};
+    use crate::universe::{advance_with_damage, BitUniverse};
+
+    #[test]
+    fn jumps_land_on_the_same_board_as_single_steps() {
+        let rules = [Rule::CONWAY, Rule::parse("highlife").unwrap(), Rule::parse("B3/S12345").unwrap()];
+        for (i, (width, height)) in [(1, 1), (7, 3), (33, 20), (64, 64), (70, 45)].iter().enumerate() {
+            for rule in rules.iter() {
+                let board = random_board(*width, *height, Grid::SQUARE, Topology::BOUNDED, &mut Random(0x2545f4914f6cdd1d + i as u64));
+                let mut hashlife = HashLife::from_board(&board, *rule).unwrap();
+                let mut bits = BitUniverse::new(board, *rule);
+                for generations in [1, 2, 5, 16, 37, 100].iter() {
+                    hashlife.advance(*generations);
+                    bits.advance(*generations);
+                    assert_eq!(hashlife.generation(), bits.generation());
+                    assert!(hashlife.to_board() == *bits.board(), "{}x{} board under {} after {} generations", width, height, rule, bits.generation());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn damage_lookahead_jumps_to_the_same_hits_as_single_steps() {
+        let model = HitModel { run_length: 1, scaled: true };
+        for seed in 1..4 {
+            // live cells only in the middle, so they need a while to reach the edges, and a glider flying to each edge
+            let middle = random_board(30, 24, Grid::SQUARE, Topology::BOUNDED, &mut Random(seed));
+            let mut board = Board::new(90, 24);
+            for y in 0..24 {
+                for x in 0..30 {
+                    board.set(x + 30, y, middle.get(x, y));
+                }
+            }
+            for (x, y) in [(11, 2), (10, 3), (10, 4), (11, 4), (12, 4), (77, 2), (78, 3), (76, 4), (77, 4), (78, 4)].iter() {
+                board.set(*x, *y, Some(PlayerNum::ONE));
+            }
+
+            let mut bits = BitUniverse::new(board.clone(), Rule::CONWAY);
+            let mut expected = Vec::new();
+            for _ in 0..200 {
+                let damage = check_for_damage(bits.board(), &model);
+                if damage.0.is_some() || damage.1.is_some() {
+                    expected.push((bits.generation(), damage));
+                }
+                bits.step();
+            }
+
+            let mut hashlife = HashLife::from_board(&board, Rule::CONWAY).unwrap();
+            assert_eq!(advance_with_damage(&mut hashlife, 200, &model), expected);
+            assert!(hashlife.to_board() == *bits.board());
+        }
+    }
+
+    #[test]
+    fn only_bounded_square_boards_are_accepted() {
+        assert!(HashLife::from_board(&Board::with_topology(10, 10, Topology::CYLINDER), Rule::CONWAY).is_err());
+        assert!(HashLife::from_board(&Board::with_shape(10, 10, Grid::HEX, Topology::BOUNDED), Rule::HEXLIFE).is_err());
+        assert!(HashLife::from_board(&Board::new(10, 10), Rule::CONWAY).is_ok());
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+
+use crate::board::Board;
+use crate::player::Player;
+
+// What a match looked like right before one of its generations was calculated.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub board: Board,
+    pub ticks: u64,
+    pub players: [Player; 2]
+}
+
+// A bounded ring of the most recent snapshots, oldest first.
+// While scrubbing, `position` is the snapshot being looked at, or the length of the ring for `present`,
+// what the match looked like when scrubbing started. Recording a new snapshot from there drops the ones
+// after it, so the match resumes from the earlier generation.
+#[derive(Debug, Clone)]
+pub struct History {
+    snapshots: VecDeque<Snapshot>,
+    capacity: usize,
+    position: Option<usize>,
+    present: Option<Snapshot>
+}
+
+impl History {
+    pub fn new(capacity: usize) -> History {
+        History {
+            snapshots: VecDeque::with_capacity(capacity.min(1024)),
+            capacity,
+            position: None,
+            present: None
+        }
+    }
+
+    pub fn record(&mut self, snapshot: Snapshot) {
+        self.present = None;
+        if self.capacity == 0 {return}
+
+        if let Some(position) = self.position.take() {
+            self.snapshots.truncate(position);
+        }
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    // How many generations back from the latest one the history is being looked at.
+    pub fn rewound(&self) -> usize {
+        match self.position {
+            Some(position) => self.snapshots.len() - position,
+            None => 0
+        }
+    }
+
+    // The snapshot one generation before the one being looked at. `present` is what the match looks like now,
+    // kept to come back to when scrubbing starts from the present.
+    pub fn back(&mut self, present: impl FnOnce() -> Snapshot) -> Option<&Snapshot> {
+        let position = match self.position {
+            Some(position) => position,
+            None => {
+                if self.snapshots.is_empty() {return None}
+                self.present = Some(present());
+                self.snapshots.len()
+            }
+        };
+        if position == 0 {
+            self.position = Some(0);
+            return None
+        }
+
+        self.position = Some(position - 1);
+        self.snapshots.get(position - 1)
+    }
+
+    // The snapshot one generation after the one being looked at, if the history is being scrubbed.
+    pub fn forward(&mut self) -> Option<&Snapshot> {
+        let position = self.position?;
+        if position >= self.snapshots.len() {return None}
+
+        self.position = Some(position + 1);
+        if position + 1 == self.snapshots.len() {
+            self.present.as_ref()
+        } else {
+            self.snapshots.get(position + 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::PlayerNum;
+    use crate::settings::MatchSettings;
+
+    // Snapshots are told apart by their ticks.
+    fn snapshot(ticks: u64) -> Snapshot {
+        let settings = MatchSettings::default();
+        Snapshot {
+            board: Board::new(4, 4),
+            ticks,
+            players: [Player::new(PlayerNum::ONE, &settings), Player::new(PlayerNum::TWO, &settings)]
+        }
+    }
+
+    // A full history of 3 holding the snapshots of ticks 2 to 4, with the match at tick 5.
+    fn full_history() -> History {
+        let mut history = History::new(3);
+        for ticks in 0..5 {
+            history.record(snapshot(ticks));
+        }
+        history
+    }
+
+    #[test]
+    fn scrubs_a_full_ring_back_and_forward() {
+        let mut history = full_history();
+        let backs: Vec<Option<u64>> = (0..4).map(|_| history.back(|| snapshot(5)).map(|snapshot| snapshot.ticks)).collect();
+        assert_eq!(backs, vec![Some(4), Some(3), Some(2), None]);
+        assert_eq!(history.rewound(), 3);
+
+        let forwards: Vec<Option<u64>> = (0..4).map(|_| history.forward().map(|snapshot| snapshot.ticks)).collect();
+        assert_eq!(forwards, vec![Some(3), Some(4), Some(5), None]);
+        assert_eq!(history.rewound(), 0);
+    }
+
+    #[test]
+    fn nothing_is_before_the_oldest_snapshot() {
+        let mut history = History::new(3);
+        assert!(history.back(|| snapshot(0)).is_none());
+
+        let mut history = full_history();
+        while history.back(|| snapshot(5)).is_some() {}
+        assert!(history.back(|| snapshot(5)).is_none());
+        assert_eq!(history.rewound(), 3);
+        assert_eq!(history.forward().map(|snapshot| snapshot.ticks), Some(3));
+    }
+
+    #[test]
+    fn recording_after_rewinding_drops_the_later_snapshots() {
+        let mut history = full_history();
+        history.back(|| snapshot(5));
+        history.back(|| snapshot(5));
+        history.record(snapshot(10));
+        assert_eq!(history.rewound(), 0);
+        assert!(history.forward().is_none());
+
+        let backs: Vec<Option<u64>> = (0..3).map(|_| history.back(|| snapshot(11)).map(|snapshot| snapshot.ticks)).collect();
+        assert_eq!(backs, vec![Some(10), Some(2), None]);
+    }
+}
@@ -0,0 +1,35 @@
+// Headless simulation core of "fight for your life".
+// Everything in here is independent of ggez, so the board, the rules, the damage
+// and the player state can be driven by bots, tests and tools without opening a window.
+#![allow(non_camel_case_types, clippy::upper_case_acronyms)]
+
+#[macro_use]
+mod point;
+mod board;
+mod damage;
+mod editor;
+mod game;
+mod hashlife;
+mod history;
+mod pattern;
+mod player;
+mod rules;
+mod settings;
+mod stalemate;
+mod topology;
+mod universe;
+
+pub use board::{calculate_next_generation, calculate_next_generation_into, calculate_next_generation_parallel, count_alive_neighbours, neighbours, newborn_owner, Board, Cell};
+pub use damage::{check_for_damage, check_universe_for_damage, Hit, HitModel, Hits};
+pub use editor::{Editor, EDITOR_HEIGHT, EDITOR_WIDTH};
+pub use game::{make_damage_calculations, Game, GameState, Outcome, GENERATION_CALCULATION_DELAY, TICKS_PER_SECOND, TICK_DURATION};
+pub use hashlife::HashLife;
+pub use history::{History, Snapshot};
+pub use pattern::{Orientation, Pattern, PatternError};
+pub use player::{Direction, Player, PlayerNum, PlayerStats, SelectionTool, SELECTION_SLOTS};
+pub use point::Point2u;
+pub use rules::{Rule, RULE_PRESETS};
+pub use settings::{MatchSettings, SettingsError};
+pub use stalemate::Stalemate;
+pub use topology::{Grid, Topology};
+pub use universe::{advance_with_damage, BitUniverse, Universe};
@@ -0,0 +1,462 @@
+use std::fmt;
+
+use crate::board::Board;
+use crate::point::Point2u;
+use crate::rules::Rule;
+use crate::topology::Grid;
+
+// RLE lines are wrapped at this many characters.
+const RLE_LINE_LENGTH: usize = 70;
+
+// Patterns read from files can be at most this many cells wide and high.
+const MAX_PATTERN_SIZE: usize = 1024;
+
+// The patterns every game starts with, as rows of `O` for alive and `.` for dead cells.
+const BUILT_IN_PATTERNS: [(&str, &[&str]); 11] = [
+    ("glider", &[
+        ".O.",
+        "..O",
+        "OOO"]),
+    ("LWSS", &[
+        ".O..O",
+        "O....",
+        "O...O",
+        "OOOO."]),
+    ("MWSS", &[
+        "...O..",
+        ".O...O",
+        "O.....",
+        "O....O",
+        "OOOOO."]),
+    ("HWSS", &[
+        "...OO..",
+        ".O....O",
+        "O......",
+        "O.....O",
+        "OOOOOO."]),
+    ("block", &[
+        "OO",
+        "OO"]),
+    ("beehive", &[
+        ".OO.",
+        "O..O",
+        ".OO."]),
+    ("blinker", &[
+        "OOO"]),
+    ("toad", &[
+        ".OOO",
+        "OOO."]),
+    ("beacon", &[
+        "OO..",
+        "O...",
+        "...O",
+        "..OO"]),
+    ("R-pentomino", &[
+        ".OO",
+        "OO.",
+        ".O."]),
+    ("Gosper glider gun", &[
+        "........................O",
+        "......................O.O",
+        "............OO......OO............OO",
+        "...........O...O....OO............OO",
+        "OO........O.....O...OO",
+        "OO........O...O.OO....O.O",
+        "..........O.....O.......O",
+        "...........O...O",
+        "............OO"])
+];
+
+// A named shape of alive cells, relative to the top left corner of the `width` x `height` box around it.
+// `rule` and `grid` are the rule and the grid the pattern was made for, when its file says. Files give the grid
+// along with the rule, as an `H` at the end of it for hex grids. Hex patterns start on an even row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub name: String,
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<Point2u>,
+    pub rule: Option<Rule>,
+    pub grid: Option<Grid>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternError {
+    pub line: Option<usize>,
+    pub message: String
+}
+
+// How a pattern is turned before it is stamped: first mirrored left to right, then rotated clockwise
+// by `turns` quarter turns on a square grid or sixth turns on a hex grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Orientation {
+    pub turns: u8,
+    pub mirrored: bool
+}
+
+impl Orientation {
+    pub fn rotated(self, grid: Grid) -> Orientation {
+        Orientation { turns: (self.turns + 1) % grid.turns(), ..self }
+    }
+
+    // How many degrees the pattern is turned on `grid`.
+    pub fn degrees(&self, grid: Grid) -> u32 {
+        (self.turns % grid.turns()) as u32 * 360 / grid.turns() as u32
+    }
+
+    pub fn flipped(self) -> Orientation {
+        Orientation { mirrored: !self.mirrored, ..self }
+    }
+}
+
+impl Pattern {
+    pub fn built_in() -> Vec<Pattern> {
+        BUILT_IN_PATTERNS.iter().map(|(name, rows)| Pattern::from_rows(name, rows.iter().copied())).collect()
+    }
+
+    // Builds a pattern out of rows where `O` or `*` is an alive cell and anything else a dead one.
+    pub fn from_rows<'a>(name: &str, rows: impl Iterator<Item = &'a str>) -> Pattern {
+        let mut pattern = Pattern { name: name.to_string(), width: 0, height: 0, cells: Vec::new(), rule: None, grid: None };
+        for (y, row) in rows.enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == 'O' || c == '*' {
+                    pattern.cells.push(pointu![x, y]);
+                }
+            }
+            pattern.width = pattern.width.max(row.chars().count());
+            pattern.height = y + 1;
+        }
+
+        pattern
+    }
+
+    // The alive cells of the `width` x `height` region of the board starting at (x0, y0), whoever owns them.
+    // The region must be inside the board. On a hex grid a region starting on an odd row gets the row above it too.
+    pub fn from_board(name: &str, board: &Board, x0: usize, y0: usize, width: usize, height: usize, rule: Rule) -> Pattern {
+        let (y0, height) = if board.grid() == Grid::HEX && !y0.is_multiple_of(2) {(y0 - 1, height + 1)} else {(y0, height)};
+        let mut pattern = Pattern { name: name.to_string(), width, height, cells: Vec::new(), rule: Some(rule), grid: Some(board.grid()) };
+        for y in 0..height {
+            for x in 0..width {
+                if board.is_alive(x0 + x, y0 + y) {
+                    pattern.cells.push(pointu![x, y]);
+                }
+            }
+        }
+
+        pattern
+    }
+
+    // Writes the pattern in RLE, with its name and, if it has one, its rule.
+    pub fn to_rle(&self) -> String {
+        let mut rle = format!("#N {}\nx = {}, y = {}", self.name, self.width, self.height);
+        if let Some(rule) = self.rule {
+            let hex = if self.grid == Some(Grid::HEX) {"H"} else {""};
+            rle += &format!(", rule = {}{}", rule, hex);
+        }
+        rle.push('\n');
+
+        let mut alive = vec![vec![false; self.width]; self.height];
+        for p in self.cells.iter() {
+            alive[p.y][p.x] = true;
+        }
+
+        // runs of `b`, `o` and `$`, leaving out the dead cells at the end of every row and the empty rows at the end
+        let mut runs: Vec<(usize, char)> = Vec::new();
+        let mut push = |count: usize, tag: char| match runs.last_mut() {
+            Some((last_count, last_tag)) if *last_tag == tag => *last_count += count,
+            _ => runs.push((count, tag))
+        };
+        let last_row = alive.iter().rposition(|row| row.contains(&true));
+        for (y, row) in alive.iter().enumerate().take(last_row.map_or(0, |last| last + 1)) {
+            if y > 0 {push(1, '$')}
+            let row_end = row.iter().rposition(|cell| *cell).map_or(0, |last| last + 1);
+            for cell in row[..row_end].iter() {
+                push(1, if *cell {'o'} else {'b'});
+            }
+        }
+
+        let mut line = String::new();
+        for item in runs.iter().map(|(count, tag)| if *count > 1 {format!("{}{}", count, tag)} else {tag.to_string()}).chain(Some("!".to_string())) {
+            if line.len() + item.len() > RLE_LINE_LENGTH {
+                rle += &line;
+                rle.push('\n');
+                line.clear();
+            }
+            line += &item;
+        }
+        rle += &line;
+        rle.push('\n');
+
+        rle
+    }
+
+    // Parses a pattern file, in RLE when `file_name` ends in `.rle` and in plaintext when it ends in `.cells`.
+    // The pattern is named after the file unless the file names it.
+    pub fn parse(file_name: &str, text: &str) -> Result<Pattern, PatternError> {
+        let (stem, extension) = match file_name.rfind('.') {
+            Some(index) => (&file_name[..index], file_name[index + 1..].to_ascii_lowercase()),
+            None => (file_name, String::new())
+        };
+        match extension.as_str() {
+            "rle" => Pattern::parse_rle(stem, text),
+            "cells" => Pattern::parse_cells(stem, text),
+            _ => Err(PatternError { line: None, message: format!("`{}` is not an .rle or .cells file", file_name) })
+        }
+    }
+
+    // Parses the plaintext format: `!` starts a comment line, `!Name: ` names the pattern,
+    // every other line is a row where `O` is an alive cell and `.` a dead one.
+    pub fn parse_cells(name: &str, text: &str) -> Result<Pattern, PatternError> {
+        let mut name = name.to_string();
+        let mut rows = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            if let Some(comment) = line.strip_prefix('!') {
+                if let Some(pattern_name) = comment.strip_prefix("Name:") {
+                    name = pattern_name.trim().to_string();
+                }
+                continue
+            }
+            if let Some(c) = line.chars().find(|c| !matches!(c, '.' | 'O' | '*')) {
+                return Err(PatternError { line: Some(i + 1), message: format!("unexpected `{}`, rows can only have `.` and `O`", c) })
+            }
+            rows.push(line);
+        }
+
+        let pattern = Pattern::from_rows(&name, rows.into_iter());
+        if pattern.cells.is_empty() {
+            return Err(PatternError { line: None, message: "the pattern has no alive cells".to_string() })
+        }
+        Ok(pattern)
+    }
+
+    // Parses run length encoded patterns: `#N` names the pattern, the `x = 3, y = 3, rule = B3/S23` header gives
+    // its size and rule, and the runs that follow, up to a `!`, are a count and `b` for dead cells, `o` for alive
+    // ones or `$` for the end of a row.
+    pub fn parse_rle(name: &str, text: &str) -> Result<Pattern, PatternError> {
+        let error = |line: usize, message: String| PatternError { line: Some(line + 1), message };
+        let too_big = |line: usize| error(line, format!("patterns can be at most {} x {} cells", MAX_PATTERN_SIZE, MAX_PATTERN_SIZE));
+        let mut pattern = Pattern { name: name.to_string(), width: 0, height: 0, cells: Vec::new(), rule: None, grid: None };
+        let mut header = false;
+        let (mut x, mut y) = (0, 0);
+        let mut count: Option<usize> = None;
+
+        'lines: for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {continue}
+            if let Some(comment) = line.strip_prefix('#') {
+                match comment.chars().next() {
+                    Some('N') => pattern.name = comment[1..].trim().to_string(),
+                    Some('r') => {
+                        let (rule, grid) = parse_rle_rule(comment[1..].trim()).map_err(|e| error(i, e))?;
+                        pattern.rule = Some(rule);
+                        pattern.grid = Some(grid);
+                    },
+                    _ => ()
+                }
+                continue
+            }
+
+            if !header {
+                // the rule goes last and can have commas of its own
+                let (fields, rule) = match line.find("rule") {
+                    Some(index) => (line[..index].trim_end().trim_end_matches(','), Some(&line[index..])),
+                    None => (line, None)
+                };
+                for field in fields.split(',').chain(rule) {
+                    let (key, value) = match field.find('=') {
+                        Some(index) => (field[..index].trim(), field[index + 1..].trim()),
+                        None => return Err(error(i, format!("expected the `x = ..., y = ...` header, found `{}`", line)))
+                    };
+                    let size = || match value.parse::<usize>() {
+                        Ok(size) if size > MAX_PATTERN_SIZE => Err(too_big(i)),
+                        Ok(size) => Ok(size),
+                        Err(_) => Err(error(i, format!("`{}` must be a whole number, found `{}`", key, value)))
+                    };
+                    match key {
+                        "x" => pattern.width = size()?,
+                        "y" => pattern.height = size()?,
+                        "rule" => {
+                            let (rule, grid) = parse_rle_rule(value).map_err(|e| error(i, e))?;
+                            pattern.rule = Some(rule);
+                            pattern.grid = Some(grid);
+                        },
+                        _ => return Err(error(i, format!("unknown header field `{}`", key)))
+                    }
+                }
+                header = true;
+                continue
+            }
+
+            for c in line.chars() {
+                match c {
+                    '0'..='9' => {
+                        let digit = c.to_digit(10).unwrap_or(0) as usize;
+                        count = count.unwrap_or(0).checked_mul(10).and_then(|count| count.checked_add(digit));
+                        if count.is_none_or(|count| count > MAX_PATTERN_SIZE) {return Err(too_big(i))}
+                        continue
+                    },
+                    'b' | '.' | 'o' | 'A' if x + count.unwrap_or(1) > MAX_PATTERN_SIZE => return Err(too_big(i)),
+                    '$' if y + count.unwrap_or(1) >= MAX_PATTERN_SIZE => return Err(too_big(i)),
+                    'b' | '.' => x += count.unwrap_or(1),
+                    'o' | 'A' => {
+                        for _ in 0..count.unwrap_or(1) {
+                            pattern.cells.push(pointu![x, y]);
+                            x += 1;
+                        }
+                        // patterns bigger than their header say are accepted
+                        pattern.width = pattern.width.max(x);
+                        pattern.height = pattern.height.max(y + 1);
+                    },
+                    '$' => {
+                        y += count.unwrap_or(1);
+                        x = 0;
+                    },
+                    '!' => break 'lines,
+                    c if c.is_whitespace() => continue,
+                    c => return Err(error(i, format!("unexpected `{}`, only two state patterns with `b`, `o` and `$` are supported", c)))
+                }
+                count = None;
+            }
+        }
+
+        if !header {
+            return Err(PatternError { line: None, message: "missing the `x = ..., y = ...` header".to_string() })
+        }
+        if pattern.cells.is_empty() {
+            return Err(PatternError { line: None, message: "the pattern has no alive cells".to_string() })
+        }
+        Ok(pattern)
+    }
+
+    // The cells of the pattern turned by `orientation` on `grid`, relative to the top left corner of the box
+    // around them, and the width and height of that box. The box also holds the turned corners of the pattern,
+    // so empty rows and columns at its edges are kept.
+    pub fn oriented(&self, orientation: Orientation, grid: Grid) -> (Vec<Point2u>, usize, usize) {
+        let corners = [(0, 0), (self.width as isize - 1, self.height as isize - 1)];
+        let mut cells: Vec<(isize, isize)> = corners.iter().copied().chain(self.cells.iter().map(|p| (p.x as isize, p.y as isize))).collect();
+        if orientation.mirrored {
+            cells.iter_mut().for_each(|p| *p = grid.mirror(*p, true));
+        }
+        for _ in 0..orientation.turns % grid.turns() {
+            cells.iter_mut().for_each(|p| *p = grid.rotate(*p, (0, 0)));
+        }
+
+        // moved up as a whole so the top row is an even one again, then to the left
+        let upper = cells.iter().map(|p| p.1).min().unwrap_or(0);
+        cells.iter_mut().for_each(|p| *p = grid.translate(*p, (0, -upper)));
+        let left = cells.iter().map(|p| p.0).min().unwrap_or(0);
+        let width = cells.iter().map(|p| p.0 - left + 1).max().unwrap_or(0) as usize;
+        let height = cells.iter().map(|p| p.1 + 1).max().unwrap_or(0) as usize;
+        let cells = cells[corners.len()..].iter().map(|p| pointu![(p.0 - left) as usize, p.1 as usize]).collect();
+
+        (cells, width, height)
+    }
+}
+
+// Rules in RLE files can end with an `H` for hex grids, like `B2/S34H`, and with a `:` and the size of the board
+// they were made on, like `B3/S23:P40,30`.
+fn parse_rle_rule(text: &str) -> Result<(Rule, Grid), String> {
+    let rule = text.split(':').next().unwrap_or("").trim();
+    match rule.strip_suffix(['H', 'h']) {
+        Some(rule) => Ok((Rule::parse(rule)?, Grid::HEX)),
+        None => Ok((Rule::parse(rule)?, Grid::SQUARE))
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message)
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::PlayerNum;
+    use crate::topology::{axial_shape, from_axial, Topology};
+
+    const GOSPER_GLIDER_GUN_RLE: &str = "#N Gosper glider gun
+x = 36, y = 9, rule = B3/S23
+24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b
+obo$10bo5bo7bo$11bo3bo$12b2o!
+";
+
+    #[test]
+    fn reads_the_canonical_glider_gun() {
+        let pattern = Pattern::parse("gun.rle", GOSPER_GLIDER_GUN_RLE).unwrap();
+        let built_in = Pattern::built_in().into_iter().find(|pattern| pattern.name == "Gosper glider gun").unwrap();
+        assert_eq!((pattern.width, pattern.height), (36, 9));
+        assert_eq!(pattern.cells, built_in.cells);
+        assert_eq!(pattern.rule, Some(Rule::CONWAY));
+        assert_eq!(pattern.grid, Some(Grid::SQUARE));
+    }
+
+    #[test]
+    fn rle_round_trips() {
+        for pattern in Pattern::built_in().iter() {
+            let pattern = Pattern { rule: Some(Rule::CONWAY), grid: Some(Grid::SQUARE), ..pattern.clone() };
+            assert_eq!(Pattern::parse("pattern.rle", &pattern.to_rle()), Ok(pattern));
+        }
+
+        let mut board = Board::with_shape(8, 8, Grid::HEX, Topology::BOUNDED);
+        for (x, y) in [(2, 3), (3, 3), (2, 4), (5, 6)].iter() {
+            board.set(*x, *y, Some(PlayerNum::TWO));
+        }
+        let pattern = Pattern::from_board("hex", &board, 1, 3, 6, 4, Rule::HEXLIFE);
+        assert!(pattern.to_rle().contains("rule = B2/S34H"));
+        assert_eq!(Pattern::parse("hex.rle", &pattern.to_rle()), Ok(pattern));
+    }
+
+    #[test]
+    fn hex_orientations_keep_the_shape() {
+        let r_pentomino = Pattern::built_in().into_iter().find(|pattern| pattern.name == "R-pentomino").unwrap();
+        let mirrored = Orientation { turns: 0, mirrored: true };
+        let (cells, _, _) = r_pentomino.oriented(mirrored, Grid::HEX);
+        assert_eq!(axial_shape(&cells), vec![(0, 0), (0, 1), (0, 2), (1, 0), (1, 1)]);
+
+        // a sixth turn in axial coordinates is (q, r) -> (-r, q + r)
+        let (cells, _, _) = r_pentomino.oriented(Orientation::default().rotated(Grid::HEX), Grid::HEX);
+        let turned: Vec<Point2u> = axial_shape(&r_pentomino.cells).into_iter()
+            .map(|(q, r)| from_axial(-r + 10, q + r))
+            .map(|(x, y)| pointu![x as usize, y as usize])
+            .collect();
+        assert_eq!(axial_shape(&cells), axial_shape(&turned));
+
+        let mut pattern = r_pentomino.clone();
+        for _ in 0..Grid::HEX.turns() {
+            let (cells, width, height) = pattern.oriented(Orientation::default().rotated(Grid::HEX), Grid::HEX);
+            pattern = Pattern { cells, width, height, ..pattern };
+        }
+        assert_eq!(axial_shape(&pattern.cells), axial_shape(&r_pentomino.cells));
+    }
+
+    #[test]
+    fn reads_plaintext_files() {
+        let pattern = Pattern::parse("glider.cells", "!Name: my glider\n!\n.O\n..O\nOOO\n").unwrap();
+        assert_eq!(pattern.name, "my glider");
+        assert_eq!((pattern.width, pattern.height), (3, 3));
+        assert_eq!(pattern.cells.len(), 5);
+    }
+
+    #[test]
+    fn malformed_files_are_errors() {
+        let error = |file_name: &str, text: &str| Pattern::parse(file_name, text).unwrap_err();
+        assert_eq!(error("a.rle", "bo$2o!").line, Some(1));
+        assert_eq!(error("a.rle", "x = 3, y = 3\nbo$2q!").line, Some(2));
+        assert_eq!(error("a.rle", "x = 3, y = 3, rule = B9/S23\no!").line, Some(1));
+        assert_eq!(error("a.rle", "x = 3, y = 3\n3b!").line, None);
+        assert_eq!(error("a.rle", "x = 3, y = 3\n99999999999999999999999o!").line, Some(2));
+        assert_eq!(error("a.rle", "x = 3, y = 3\n100000000o!").line, Some(2));
+        assert_eq!(error("a.rle", "x = 3, y = 3\n1000$1000$o!").line, Some(2));
+        assert_eq!(error("a.rle", "x = 99999999, y = 3\no!").line, Some(1));
+        assert_eq!(error("a.cells", ".O\nOX\n").line, Some(2));
+        assert_eq!(error("a.cells", "!only a comment\n").line, None);
+        assert_eq!(error("a.txt", "O").line, None);
+    }
+}
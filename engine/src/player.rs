@@ -0,0 +1,638 @@
+use std::fmt;
+
+use crate::pattern::Orientation;
+use crate::point::Point2u;
+use crate::settings::MatchSettings;
+use crate::topology::Grid;
+
+// How many selections a player can save to recall later.
+pub const SELECTION_SLOTS: usize = 5;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum PlayerNum {
+    ONE,
+    TWO
+}
+
+impl PlayerNum {
+    pub fn opponent(&self) -> PlayerNum {
+        match self {
+            PlayerNum::ONE => PlayerNum::TWO,
+            PlayerNum::TWO => PlayerNum::ONE
+        }
+    }
+}
+
+// What the player achieved during the match.
+#[derive(Debug, Clone, Default)]
+pub struct PlayerStats {
+    pub cells_placed: usize,
+    pub hits_dealt: usize,
+    pub own_hits: usize
+}
+
+// The diagonals are what make the 6 directions of a hex grid together with LEFT and RIGHT.
+// UP and DOWN stay in the same column, which on a hex grid zigzags between the two upper or lower neighbours.
+#[derive(Debug)]
+pub enum Direction {
+    UP,
+    RIGHT,
+    LEFT,
+    DOWN,
+    UP_LEFT,
+    UP_RIGHT,
+    DOWN_LEFT,
+    DOWN_RIGHT
+}
+
+// What the selection tools select between the anchor and the hovering square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelectionTool {
+    #[default]
+    RECTANGLE,
+    FILLED_RECTANGLE,
+    LINE
+}
+
+impl SelectionTool {
+    pub fn next(self) -> SelectionTool {
+        match self {
+            SelectionTool::RECTANGLE => SelectionTool::FILLED_RECTANGLE,
+            SelectionTool::FILLED_RECTANGLE => SelectionTool::LINE,
+            SelectionTool::LINE => SelectionTool::RECTANGLE
+        }
+    }
+}
+
+impl fmt::Display for SelectionTool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectionTool::RECTANGLE => write!(f, "rectangle"),
+            SelectionTool::FILLED_RECTANGLE => write!(f, "filled rectangle"),
+            SelectionTool::LINE => write!(f, "line")
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Player {
+    pub player_num: PlayerNum,
+    // Seconds left before the player can commit their selected squares again.
+    pub movement_cooldown_time: f32,
+    pub hp: usize,
+    pub max_hp: usize,
+    // Spent to place cells, regenerates over time up to `max_energy`.
+    pub energy: f32,
+    pub max_energy: f32,
+    pub hovering_square: Point2u,
+    pub selected_squares: Vec<Point2u>,
+    // Where the selection tool was started from, until it is used.
+    pub selection_anchor: Option<Point2u>,
+    pub selection_tool: SelectionTool,
+    // While dragging, moving the hovering square takes the selected squares along with it.
+    pub dragging_selection: bool,
+    // Saved selections, as the moves from the hovering square at the time they were saved to each square,
+    // so on a hex grid they keep their shape when recalled on a row of the other parity.
+    pub selection_slots: [Option<Vec<(isize, isize)>>; SELECTION_SLOTS],
+    // The pattern of the game's library the player stamps, and how it is turned.
+    pub pattern: usize,
+    pub orientation: Orientation,
+    pub stats: PlayerStats,
+    grid: Grid,
+    _x_left_bound: usize,
+    _x_right_bound: usize,
+    _y_upper_bound: usize,
+    _y_lower_bound: usize,
+}
+
+impl Player {
+    pub fn new(player_num: PlayerNum, settings: &MatchSettings) -> Self {
+        let (_x_left_bound, _x_right_bound, _y_upper_bound, _y_lower_bound) = settings.player_area(&player_num);
+
+        let hovering_square_point = pointu![(_x_left_bound + _x_right_bound)/2, (_y_upper_bound + _y_lower_bound)/2];
+
+        Player {
+            player_num,
+            movement_cooldown_time: 0.0,
+            hp: settings.max_hp,
+            max_hp: settings.max_hp,
+            energy: settings.max_energy as f32,
+            max_energy: settings.max_energy as f32,
+            hovering_square : hovering_square_point,
+            selected_squares: Vec::with_capacity(20),
+            selection_anchor: None,
+            selection_tool: SelectionTool::default(),
+            dragging_selection: false,
+            selection_slots: Default::default(),
+            pattern: 0,
+            orientation: Orientation::default(),
+            stats: PlayerStats::default(),
+            grid: settings.grid,
+            _x_left_bound,
+            _x_right_bound,
+            _y_upper_bound,
+            _y_lower_bound
+        }
+    }
+
+    // (left, right, upper, lower) bounds of the area this player can select squares in, inclusive.
+    pub fn area_bounds(&self) -> (usize, usize, usize, usize) {
+        (self._x_left_bound, self._x_right_bound, self._y_upper_bound, self._y_lower_bound)
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.hp == 0
+    }
+
+    pub fn take_damage(&mut self, amount: usize) {
+        self.hp = self.hp.saturating_sub(amount);
+    }
+
+    pub fn regenerate_energy(&mut self, amount: f32) {
+        self.energy = (self.energy + amount).min(self.max_energy);
+    }
+
+    pub fn cool_down(&mut self, seconds: f32) {
+        self.movement_cooldown_time = (self.movement_cooldown_time - seconds).max(0.0);
+    }
+
+    pub fn toggle_hovered_square(&mut self) {
+        let index = self.selected_squares.iter().position(|x| *x == self.hovering_square);
+        if let Some(i) = index {
+            self.selected_squares.remove(i);
+        } else {
+            self.selected_squares.push(self.hovering_square);
+        }
+    }
+
+    pub fn in_area(&self, p: Point2u) -> bool {
+        p.x >= self._x_left_bound && p.x <= self._x_right_bound && p.y >= self._y_upper_bound && p.y <= self._y_lower_bound
+    }
+
+    // Starts the selection tool at the hovering square, or, when it was already started, selects its squares.
+    pub fn use_selection_tool(&mut self) {
+        if self.selection_anchor.is_none() {
+            self.selection_anchor = Some(self.hovering_square);
+            return
+        }
+
+        for square in self.tool_squares() {
+            if !self.selected_squares.contains(&square) {
+                self.selected_squares.push(square);
+            }
+        }
+        self.selection_anchor = None;
+    }
+
+    // The squares the selection tool covers from its anchor to the hovering square, none when it was not started.
+    pub fn tool_squares(&self) -> Vec<Point2u> {
+        let (anchor, cursor) = match self.selection_anchor {
+            Some(anchor) => (anchor, self.hovering_square),
+            None => return Vec::new()
+        };
+        let (left, right) = (anchor.x.min(cursor.x), anchor.x.max(cursor.x));
+        let (upper, lower) = (anchor.y.min(cursor.y), anchor.y.max(cursor.y));
+
+        let squares: Vec<Point2u> = match self.selection_tool {
+            SelectionTool::RECTANGLE => (upper..=lower)
+                .flat_map(|y| (left..=right).map(move |x| pointu![x, y]))
+                .filter(|p| p.x == left || p.x == right || p.y == upper || p.y == lower)
+                .collect(),
+            SelectionTool::FILLED_RECTANGLE => (upper..=lower)
+                .flat_map(|y| (left..=right).map(move |x| pointu![x, y]))
+                .collect(),
+            SelectionTool::LINE => line_squares(anchor, cursor)
+        };
+        squares.into_iter().filter(|p| self.in_area(*p)).collect()
+    }
+
+    // Drops every selected square and the started selection tool.
+    pub fn clear_selection(&mut self) {
+        self.selected_squares.clear();
+        self.selection_anchor = None;
+        self.dragging_selection = false;
+    }
+
+    // Saves the shape of the selection, relative to the hovering square, to a slot.
+    pub fn save_selection(&mut self, slot: usize) -> Result<(), String> {
+        if self.selected_squares.is_empty() {
+            return Err("select some squares to save first".to_string())
+        }
+
+        let cursor = (self.hovering_square.x as isize, self.hovering_square.y as isize);
+        self.selection_slots[slot] = Some(self.selected_squares.iter().map(|p| self.grid.displacement(cursor, (p.x as isize, p.y as isize))).collect());
+        Ok(())
+    }
+
+    // Adds the selection saved in a slot to the selection, around the hovering square and clipped to the area.
+    pub fn recall_selection(&mut self, slot: usize) -> Result<(), String> {
+        let offsets = match &self.selection_slots[slot] {
+            Some(offsets) => offsets,
+            None => return Err(format!("nothing saved in slot {}", slot + 1))
+        };
+
+        let cursor = (self.hovering_square.x as isize, self.hovering_square.y as isize);
+        let squares: Vec<Point2u> = offsets.iter()
+            .map(|offset| self.grid.translate(cursor, *offset))
+            .filter(|(x, y)| *x >= 0 && *y >= 0)
+            .map(|(x, y)| pointu![x as usize, y as usize])
+            .filter(|p| self.in_area(*p))
+            .collect();
+        for square in squares {
+            if !self.selected_squares.contains(&square) {
+                self.selected_squares.push(square);
+            }
+        }
+        Ok(())
+    }
+
+    // Turns the selected squares clockwise: a quarter turn keeping the top left corner of the box around them
+    // on a square grid, and a sixth of a turn around the hovering square on a hex grid, where quarter turns
+    // would change their shape.
+    pub fn rotate_selection(&mut self) {
+        let (left, _, upper, lower) = match self.selection_box() {
+            Some(bounds) => bounds,
+            None => return
+        };
+        let pivot = match self.grid {
+            Grid::SQUARE => (left as isize, upper as isize),
+            Grid::HEX => (self.hovering_square.x as isize, self.hovering_square.y as isize)
+        };
+        let shift_x = if self.grid == Grid::SQUARE {(lower - upper) as isize} else {0};
+        let squares = self.selected_squares.iter()
+            .map(|p| self.grid.rotate((p.x as isize, p.y as isize), pivot))
+            .map(|(x, y)| (x + shift_x, y))
+            .collect();
+        self.place_selection(squares);
+    }
+
+    // Mirrors the selected squares left to right, or top to bottom, inside the box around them.
+    // On a hex grid the odd rows are shifted half a cell, so columns are measured in half cells, and cells and rows
+    // are only mirrored onto whole cells and rows of the same parity. That needs an even sum of the first and the last
+    // half column or row: an odd one is rounded to the even number next to it that is a multiple of 4, which the
+    // mirrored selection rounds to again, so mirroring twice gives back the same squares.
+    pub fn mirror_selection(&mut self, horizontally: bool) {
+        let (_, _, upper, lower) = match self.selection_box() {
+            Some(bounds) => bounds,
+            None => return
+        };
+        let hex = self.grid == Grid::HEX;
+        let sum = if horizontally {
+            let half_columns = self.selected_squares.iter().map(|p| 2 * p.x + if hex {p.y & 1} else {0});
+            half_columns.clone().min().unwrap_or(0) + half_columns.max().unwrap_or(0)
+        } else {
+            upper + lower
+        };
+        let sum = if hex && !sum.is_multiple_of(2) {
+            if (sum + 1).is_multiple_of(4) {sum + 1} else {sum - 1}
+        } else {
+            sum
+        };
+        let shift = if horizontally {((sum / 2) as isize, 0)} else {(0, sum as isize)};
+        let squares = self.selected_squares.iter()
+            .map(|p| self.grid.mirror((p.x as isize, p.y as isize), horizontally))
+            .map(|p| self.grid.translate(p, shift))
+            .collect();
+        self.place_selection(squares);
+    }
+
+    // (left, right, upper, lower) bounds of the box around the selected squares, inclusive.
+    fn selection_box(&self) -> Option<(usize, usize, usize, usize)> {
+        if self.selected_squares.is_empty() {return None}
+
+        let xs = self.selected_squares.iter().map(|p| p.x);
+        let ys = self.selected_squares.iter().map(|p| p.y);
+        Some((xs.clone().min().unwrap_or(0), xs.max().unwrap_or(0), ys.clone().min().unwrap_or(0), ys.max().unwrap_or(0)))
+    }
+
+    // Makes the squares the new selection, shifted back inside the area if they stick out of it.
+    // On a hex grid they are only shifted by an even number of rows, to keep their shape.
+    // When they cannot fit in the area the selection is left as it was.
+    fn place_selection(&mut self, squares: Vec<(isize, isize)>) {
+        let (left, right, upper, lower) = (self._x_left_bound as isize, self._x_right_bound as isize, self._y_upper_bound as isize, self._y_lower_bound as isize);
+        let min_y = squares.iter().map(|p| p.1).min().unwrap_or(upper);
+        let max_y = squares.iter().map(|p| p.1).max().unwrap_or(upper);
+        let mut shift_y = if min_y < upper {upper - min_y} else if max_y > lower {lower - max_y} else {0};
+        if self.grid == Grid::HEX && shift_y % 2 != 0 {
+            shift_y += shift_y.signum();
+        }
+        let min_x = squares.iter().map(|p| p.0).min().unwrap_or(left);
+        let max_x = squares.iter().map(|p| p.0).max().unwrap_or(left);
+        let shift_x = if min_x < left {left - min_x} else if max_x > right {right - max_x} else {0};
+        let squares: Vec<(isize, isize)> = squares.into_iter().map(|(x, y)| (x + shift_x, y + shift_y)).collect();
+
+        if squares.iter().any(|(x, y)| *x < left || *x > right || *y < upper || *y > lower) {return}
+        self.selected_squares = squares.into_iter().map(|(x, y)| pointu![x as usize, y as usize]).collect();
+    }
+
+    // Where a square ends up one step in a direction, the way the hovering square moves, if that is still in the area.
+    fn step(&self, p: Point2u, dir: &Direction) -> Option<Point2u> {
+        let (dx, dy) = match dir {
+            // straight up and down on a hex grid is 2 rows, 1 row would zigzag and change the shape
+            Direction::UP if self.grid == Grid::HEX => (0, -2),
+            Direction::DOWN if self.grid == Grid::HEX => (0, 2),
+            Direction::UP => (0, -1),
+            Direction::RIGHT => (1, 0),
+            Direction::DOWN => (0, 1),
+            Direction::LEFT => (-1, 0),
+            Direction::UP_LEFT => (self.grid.diagonal_dx(p.y, false), -1),
+            Direction::UP_RIGHT => (self.grid.diagonal_dx(p.y, true), -1),
+            Direction::DOWN_LEFT => (self.grid.diagonal_dx(p.y, false), 1),
+            Direction::DOWN_RIGHT => (self.grid.diagonal_dx(p.y, true), 1)
+        };
+        let (x, y) = (p.x as isize + dx, p.y as isize + dy);
+        if x < 0 || y < 0 {return None}
+
+        Some(pointu![x as usize, y as usize]).filter(|p| self.in_area(*p))
+    }
+
+    // Moves the selected squares and the hovering square together, until the selection reaches the edge of the area.
+    fn drag_selection(&mut self, dir: Direction, amount: usize) {
+        for _ in 0..amount {
+            let moved: Option<Vec<Point2u>> = self.selected_squares.iter().map(|p| self.step(*p, &dir)).collect();
+            match moved {
+                Some(squares) => self.selected_squares = squares,
+                None => return
+            }
+            if let Some(hovering_square) = self.step(self.hovering_square, &dir) {
+                self.hovering_square = hovering_square;
+            }
+        }
+    }
+
+    pub fn move_hover(&mut self, dir: Direction, mut amount: usize) {
+        if self.dragging_selection && !self.selected_squares.is_empty() {
+            return self.drag_selection(dir, amount)
+        }
+
+        match dir {
+            Direction::UP => {
+                if amount > self.hovering_square.y {amount = self.hovering_square.y};
+                if self.hovering_square.y - amount < self._y_upper_bound {
+                    self.hovering_square.y = self._y_lower_bound;
+                } else {
+                    self.hovering_square.y -= amount;
+                }
+            },
+            Direction::RIGHT => {
+                if self.hovering_square.x + amount > self._x_right_bound {
+                    self.hovering_square.x = self._x_left_bound;
+                } else {
+                    self.hovering_square.x += amount;
+                }
+            },
+            Direction::DOWN => {
+                if self.hovering_square.y + amount > self._y_lower_bound {
+                    self.hovering_square.y = self._y_upper_bound;
+                } else {
+                    self.hovering_square.y += amount;
+                }
+            },
+            Direction::LEFT => {
+                if amount > self.hovering_square.x {amount = self.hovering_square.x};
+                if self.hovering_square.x - amount < self._x_left_bound {
+                    self.hovering_square.x = self._x_right_bound;
+                } else {
+                    self.hovering_square.x -= amount;
+                }
+            },
+            Direction::UP_LEFT | Direction::UP_RIGHT | Direction::DOWN_LEFT | Direction::DOWN_RIGHT => {
+                let up = matches!(dir, Direction::UP_LEFT | Direction::UP_RIGHT);
+                let right = matches!(dir, Direction::UP_RIGHT | Direction::DOWN_RIGHT);
+                for _ in 0..amount {
+                    match self.grid.diagonal_dx(self.hovering_square.y, right) {
+                        1 => self.move_hover(Direction::RIGHT, 1),
+                        -1 => self.move_hover(Direction::LEFT, 1),
+                        _ => ()
+                    }
+                    self.move_hover(if up {Direction::UP} else {Direction::DOWN}, 1);
+                }
+            }
+        }
+    }
+}
+
+// The squares on the straight line from `from` to `to`, both included (Bresenham's algorithm).
+fn line_squares(from: Point2u, to: Point2u) -> Vec<Point2u> {
+    let (mut x, mut y) = (from.x as isize, from.y as isize);
+    let (to_x, to_y) = (to.x as isize, to.y as isize);
+    let (dx, dy) = ((to_x - x).abs(), -(to_y - y).abs());
+    let (step_x, step_y) = ((to_x - x).signum(), (to_y - y).signum());
+    let mut error = dx + dy;
+
+    let mut squares = vec![from];
+    while (x, y) != (to_x, to_y) {
+        let doubled_error = 2 * error;
+        if doubled_error >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled_error <= dx {
+            error += dx;
+            y += step_y;
+        }
+        squares.push(pointu![x as usize, y as usize]);
+    }
+
+    squares
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::topology::axial_shape;
+
+    // Player 1 on a hex grid with `offsets` from the hovering square selected, moved down `rows` first.
+    fn hex_player(rows: usize, offsets: &[(usize, usize)]) -> Player {
+        let mut player = Player::new(PlayerNum::ONE, &MatchSettings { grid: Grid::HEX, ..MatchSettings::default() });
+        player.move_hover(Direction::DOWN, rows);
+        let (x, y) = (player.hovering_square.x - 1, player.hovering_square.y - 1);
+        player.selected_squares = offsets.iter().map(|(dx, dy)| pointu![x + dx, y + dy]).collect();
+        player
+    }
+
+    const SHAPE: [(usize, usize); 5] = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+
+    fn sorted(squares: &[Point2u]) -> Vec<(usize, usize)> {
+        let mut squares: Vec<(usize, usize)> = squares.iter().map(|p| (p.x, p.y)).collect();
+        squares.sort();
+        squares
+    }
+
+    #[test]
+    fn six_sixth_turns_are_a_full_turn() {
+        for rows in 0..2 {
+            let mut player = hex_player(rows, &SHAPE);
+            let selected = sorted(&player.selected_squares);
+            player.rotate_selection();
+            assert_ne!(sorted(&player.selected_squares), selected);
+            assert_eq!(axial_shape(&player.selected_squares).len(), SHAPE.len());
+            for _ in 1..6 {
+                player.rotate_selection();
+            }
+            assert_eq!(sorted(&player.selected_squares), selected);
+        }
+    }
+
+    #[test]
+    fn mirroring_twice_changes_nothing() {
+        for rows in 0..2 {
+            for shape in [&SHAPE[..], &SHAPE[..4], &[(1, 1), (2, 1)][..]].iter() {
+                for horizontally in [true, false].iter() {
+                    let mut player = hex_player(rows, shape);
+                    let selected = sorted(&player.selected_squares);
+                    player.mirror_selection(*horizontally);
+                    player.mirror_selection(*horizontally);
+                    assert_eq!(sorted(&player.selected_squares), selected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hex_selections_keep_their_shape() {
+        // turning and mirroring in axial coordinates, where every cell has the same neighbours
+        let moved = |shape: &[(isize, isize)], to: &dyn Fn(isize, isize) -> (isize, isize)| -> Vec<(isize, isize)> {
+            let cells: Vec<Point2u> = shape.iter()
+                .map(|(q, r)| to(*q, *r))
+                .map(|(q, r)| crate::topology::from_axial(q + 10, r + 10))
+                .map(|(x, y)| pointu![x as usize, y as usize])
+                .collect();
+            axial_shape(&cells)
+        };
+        for rows in 0..2 {
+            let mut player = hex_player(rows, &SHAPE);
+            let shape = axial_shape(&player.selected_squares);
+            player.dragging_selection = true;
+            for dir in IntoIterator::into_iter([Direction::DOWN_RIGHT, Direction::DOWN_LEFT, Direction::UP, Direction::RIGHT, Direction::UP_LEFT]) {
+                player.move_hover(dir, 1);
+                assert_eq!(axial_shape(&player.selected_squares), shape);
+            }
+
+            player.rotate_selection();
+            assert_eq!(axial_shape(&player.selected_squares), moved(&shape, &|q, r| (-r, q + r)));
+            player.mirror_selection(true);
+            let turned = moved(&shape, &|q, r| (-r, q + r));
+            assert_eq!(axial_shape(&player.selected_squares), moved(&turned, &|q, r| (-q - r, r)));
+            player.mirror_selection(false);
+            let mirrored = moved(&turned, &|q, r| (-q - r, r));
+            assert_eq!(axial_shape(&player.selected_squares), moved(&mirrored, &|q, r| (q + r, -r)));
+        }
+    }
+
+    #[test]
+    fn rectangles_are_the_border_of_filled_rectangles() {
+        let tool = |tool: SelectionTool, from: Point2u, to: Point2u| {
+            let mut player = Player::new(PlayerNum::ONE, &MatchSettings::default());
+            player.selection_tool = tool;
+            player.selection_anchor = Some(from);
+            player.hovering_square = to;
+            sorted(&player.tool_squares())
+        };
+        let (a, b) = (pointu![5, 5], pointu![9, 8]);
+        let filled = tool(SelectionTool::FILLED_RECTANGLE, a, b);
+        let outline = tool(SelectionTool::RECTANGLE, a, b);
+        assert_eq!(filled.len(), 20);
+        assert_eq!(outline.len(), 14);
+        let border: Vec<(usize, usize)> = filled.into_iter().filter(|(x, y)| *x == 5 || *x == 9 || *y == 5 || *y == 8).collect();
+        assert_eq!(outline, border);
+        assert_eq!(tool(SelectionTool::RECTANGLE, b, a), outline);
+        assert_eq!(tool(SelectionTool::RECTANGLE, pointu![5, 8], pointu![9, 5]), outline);
+    }
+
+    #[test]
+    fn lines_follow_bresenham_in_every_octant() {
+        let from = pointu![10, 10];
+        let at = |dx: isize, dy: isize| pointu![(10 + dx) as usize, (10 + dy) as usize];
+        let line: Vec<(usize, usize)> = line_squares(from, at(6, 2)).iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(line, vec![(10, 10), (11, 10), (12, 11), (13, 11), (14, 11), (15, 12), (16, 12)]);
+
+        for (dx, dy) in [(6, 2), (2, 6), (-2, 6), (-6, 2), (-6, -2), (-2, -6), (2, -6), (6, -2)].iter() {
+            let (dx, dy) = (*dx, *dy);
+            let line = line_squares(from, at(dx, dy));
+            assert_eq!(line.len() as isize, dx.abs().max(dy.abs()) + 1);
+            assert_eq!((line[0], line[line.len() - 1]), (from, at(dx, dy)));
+            for pair in line.windows(2) {
+                assert!((pair[0].x as isize - pair[1].x as isize).abs() <= 1 && (pair[0].y as isize - pair[1].y as isize).abs() <= 1);
+            }
+            // no square is more than half a square off the real line
+            for p in line.iter() {
+                let (x, y) = (p.x as f32 - 10.0, p.y as f32 - 10.0);
+                let off = if dx.abs() >= dy.abs() {y - x * dy as f32 / dx as f32} else {x - y * dx as f32 / dy as f32};
+                assert!(off.abs() <= 0.5, "({}, {}) towards ({}, {})", p.x, p.y, dx, dy);
+            }
+            // the same squares drawn from the other end
+            assert_eq!(sorted(&line_squares(at(dx, dy), from)), sorted(&line));
+        }
+    }
+
+    // Player 1 on a square grid with `offsets` from the hovering square selected.
+    fn square_player(offsets: &[(usize, usize)]) -> Player {
+        let mut player = Player::new(PlayerNum::ONE, &MatchSettings::default());
+        let (x, y) = (player.hovering_square.x - 1, player.hovering_square.y - 1);
+        player.selected_squares = offsets.iter().map(|(dx, dy)| pointu![x + dx, y + dy]).collect();
+        player
+    }
+
+    #[test]
+    fn four_quarter_turns_are_a_full_turn() {
+        let mut player = square_player(&SHAPE);
+        let selected = sorted(&player.selected_squares);
+        player.rotate_selection();
+        assert_ne!(sorted(&player.selected_squares), selected);
+        for _ in 1..4 {
+            player.rotate_selection();
+        }
+        assert_eq!(sorted(&player.selected_squares), selected);
+    }
+
+    #[test]
+    fn mirroring_square_selections_twice_changes_nothing() {
+        for horizontally in [true, false].iter() {
+            let mut player = square_player(&SHAPE);
+            let selected = sorted(&player.selected_squares);
+            player.mirror_selection(*horizontally);
+            assert_ne!(sorted(&player.selected_squares), selected);
+            player.mirror_selection(*horizontally);
+            assert_eq!(sorted(&player.selected_squares), selected);
+        }
+    }
+
+    #[test]
+    fn dragging_stops_at_the_area_bounds() {
+        let shape = |player: &Player| {
+            let (left, _, upper, _) = player.selection_box().unwrap();
+            sorted(&player.selected_squares).into_iter().map(|(x, y)| (x - left, y - upper)).collect::<Vec<_>>()
+        };
+        let mut player = square_player(&SHAPE);
+        let original = shape(&player);
+        let (left, right, upper, lower) = player.area_bounds();
+        player.dragging_selection = true;
+
+        player.move_hover(Direction::LEFT, 100);
+        assert_eq!(player.selection_box().unwrap().0, left);
+        player.move_hover(Direction::UP, 100);
+        assert_eq!(player.selection_box().unwrap().2, upper);
+        player.move_hover(Direction::RIGHT, 100);
+        assert_eq!(player.selection_box().unwrap().1, right);
+        player.move_hover(Direction::DOWN, 100);
+        assert_eq!(player.selection_box().unwrap().3, lower);
+        assert_eq!(shape(&player), original);
+        assert!(player.in_area(player.hovering_square));
+    }
+
+    #[test]
+    fn recalled_hex_selections_keep_their_shape() {
+        let mut player = hex_player(0, &SHAPE);
+        assert!(player.hovering_square.y.is_multiple_of(2));
+        let shape = axial_shape(&player.selected_squares);
+        player.save_selection(0).unwrap();
+        player.selected_squares.clear();
+
+        player.move_hover(Direction::DOWN, 3);
+        assert!(!player.hovering_square.y.is_multiple_of(2));
+        player.recall_selection(0).unwrap();
+        assert_eq!(axial_shape(&player.selected_squares), shape);
+        assert!(player.recall_selection(1).is_err());
+    }
+}
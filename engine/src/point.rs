@@ -0,0 +1,12 @@
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct Point2u {
+    pub x: usize,
+    pub y: usize
+}
+
+#[macro_export]
+macro_rules! pointu {
+    ($x:expr,$y:expr) => {
+        $crate::Point2u{x:$x,y:$y}
+    }
+}
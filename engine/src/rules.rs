@@ -0,0 +1,148 @@
+use std::fmt;
+
+// A life-like rule: a dead cell is born when its count of alive neighbours is in `birth`,
+// an alive cell survives when it is in `survival`. Bit n of each mask stands for n neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rule {
+    birth: u16,
+    survival: u16
+}
+
+// Named rules that can be used in place of a rulestring.
+pub const RULE_PRESETS: [(&str, &str); 7] = [
+    ("conway",       "B3/S23"),
+    ("highlife",     "B36/S23"),
+    ("day_and_night","B3678/S34678"),
+    ("seeds",        "B2/S"),
+    ("maze",         "B3/S12345"),
+    ("life_without_death", "B3/S012345678"),
+    // for hex grids, where cells have 6 neighbours
+    ("hexlife",      "B2/S34")
+];
+
+impl Rule {
+    pub const CONWAY: Rule = Rule { birth: 1 << 3, survival: (1 << 2) | (1 << 3) };
+    pub const HEXLIFE: Rule = Rule { birth: 1 << 2, survival: (1 << 3) | (1 << 4) };
+
+    // Parses a rulestring in B/S notation (`B36/S23`), in the older S/B notation (`23/36`),
+    // or the name of one of the RULE_PRESETS.
+    pub fn parse(text: &str) -> Result<Rule, String> {
+        let text = text.trim();
+        if let Some((_, rulestring)) = RULE_PRESETS.iter().find(|(name, _)| name.eq_ignore_ascii_case(text)) {
+            return Rule::parse(rulestring)
+        }
+
+        let parts: Vec<&str> = text.split('/').collect();
+        if parts.len() != 2 {
+            return Err(format!("`{}` is not a rulestring like `B3/S23`", text))
+        }
+
+        let (birth, survival) = match (parts[0].chars().next(), parts[1].chars().next()) {
+            (Some('B'), _) | (Some('b'), _) => (&parts[0][1..], strip_prefix(parts[1], 'S', text)?),
+            (Some('S'), _) | (Some('s'), _) => (strip_prefix(parts[1], 'B', text)?, &parts[0][1..]),
+            // S/B notation without letters, as in `23/3`
+            _ => (parts[1], parts[0])
+        };
+
+        let rule = Rule {
+            birth: parse_counts(birth, text)?,
+            survival: parse_counts(survival, text)?
+        };
+        // a cell born out of nothing would have no owner
+        if rule.is_born(0) {
+            return Err(format!("rules with B0 are not supported, found `{}`", text))
+        }
+
+        Ok(rule)
+    }
+
+    pub fn is_born(&self, alive_neighbours: usize) -> bool {
+        self.birth & (1 << alive_neighbours) != 0
+    }
+
+    pub fn survives(&self, alive_neighbours: usize) -> bool {
+        self.survival & (1 << alive_neighbours) != 0
+    }
+
+    pub fn next_state(&self, alive: bool, alive_neighbours: usize) -> bool {
+        if alive {self.survives(alive_neighbours)} else {self.is_born(alive_neighbours)}
+    }
+
+    // The preset name of this rule, if it has one.
+    pub fn name(&self) -> Option<&'static str> {
+        RULE_PRESETS.iter()
+            .find(|(_, rulestring)| Rule::parse(rulestring).as_ref() == Ok(self))
+            .map(|(name, _)| *name)
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::CONWAY
+    }
+}
+
+fn strip_prefix<'a>(part: &'a str, prefix: char, text: &str) -> Result<&'a str, String> {
+    match part.chars().next() {
+        Some(c) if c.eq_ignore_ascii_case(&prefix) => Ok(&part[1..]),
+        _ => Err(format!("expected `{}` after the `/` in rulestring `{}`", prefix, text))
+    }
+}
+
+fn parse_counts(counts: &str, text: &str) -> Result<u16, String> {
+    let mut mask = 0;
+    for c in counts.chars() {
+        match c.to_digit(10) {
+            Some(n) if n <= 8 => mask |= 1 << n,
+            _ => return Err(format!("`{}` is not a neighbour count (0-8) in rulestring `{}`", c, text))
+        }
+    }
+
+    Ok(mask)
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "B")?;
+        for n in (0..=8).filter(|n| self.is_born(*n)) {
+            write!(f, "{}", n)?;
+        }
+        write!(f, "/S")?;
+        for n in (0..=8).filter(|n| self.survives(*n)) {
+            write!(f, "{}", n)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rulestrings_round_trip() {
+        for (_, rulestring) in RULE_PRESETS.iter() {
+            assert_eq!(Rule::parse(rulestring).unwrap().to_string(), *rulestring);
+        }
+        assert_eq!(Rule::parse("B3/S").unwrap().to_string(), "B3/S");
+    }
+
+    #[test]
+    fn parses_every_notation() {
+        assert_eq!(Rule::parse("B36/S23"), Ok(Rule::parse("highlife").unwrap()));
+        assert_eq!(Rule::parse("  b3/s23 "), Ok(Rule::CONWAY));
+        assert_eq!(Rule::parse("S23/B3"), Ok(Rule::CONWAY));
+        assert_eq!(Rule::parse("23/3"), Ok(Rule::CONWAY));
+        assert_eq!(Rule::parse("HexLife"), Ok(Rule::HEXLIFE));
+        assert_eq!(Rule::CONWAY.name(), Some("conway"));
+        assert_eq!(Rule::parse("B1/S").unwrap().name(), None);
+    }
+
+    #[test]
+    fn rejects_malformed_rulestrings() {
+        for text in ["", "B3", "B3/S23/X", "B9/S23", "B3/X23", "Bx/S23", "B03/S23", "life"].iter() {
+            assert!(Rule::parse(text).is_err(), "`{}` was accepted", text);
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use std::fmt;
+
+use crate::damage::HitModel;
+use crate::game::{GENERATION_CALCULATION_DELAY, TICKS_PER_SECOND};
+use crate::player::PlayerNum;
+use crate::rules::Rule;
+use crate::topology::{Grid, Topology};
+
+// Everything that can be chosen before a match starts.
+// Settings are written as `key = value` lines, `#` starts a comment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchSettings {
+    pub board_width: usize,
+    pub board_height: usize,
+    // Width of each player's selectable area, in blocks. Derived from the board width when not set.
+    pub area_width: Option<usize>,
+    // Distance of each player's selectable area from their own edge, in blocks. Derived from the board width when not set.
+    pub area_margin: Option<usize>,
+    // A rulestring like `B36/S23` or the name of a preset like `highlife`. `hexlife` when not set on a hex grid.
+    pub rule: Rule,
+    // The shape of the cells: `square` or `hex`.
+    pub grid: Grid,
+    // How the edges of the board connect: `bounded`, `cylinder` or `torus`.
+    pub topology: Topology,
+    pub hit_model: HitModel,
+    pub max_hp: usize,
+    // HP lost per hit.
+    pub damage_per_hit: usize,
+    // Energy each player starts with and can store at most, how much of it regenerates every second,
+    // and how much placing a cell costs.
+    pub max_energy: usize,
+    pub energy_per_second: f32,
+    pub cell_cost: usize,
+    // Seconds after a commit before the player can commit again. Squares can still be selected meanwhile.
+    pub commit_cooldown: f32,
+    // Length of a timed match in seconds. When time runs out the player with more HP wins.
+    pub time_limit: Option<f32>,
+    // Seconds between two generations at the start of a match, rounded to whole ticks.
+    pub generation_delay: f32,
+    // How many past generations are kept to scrub back through while frozen, for practice sessions.
+    // 0, the default, keeps none so a competitive match cannot be undone.
+    pub history_length: usize,
+    // After this many generations in a row that repeat a recent board with nobody being hit, the match ends
+    // in favour of the player with more HP, then with more alive cells, or in a draw. 0 never ends a match this way.
+    pub stalemate_window: usize,
+    // How many threads calculate each generation, 0 for one per core.
+    pub threads: usize
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingsError {
+    pub line: Option<usize>,
+    pub message: String
+}
+
+impl Default for MatchSettings {
+    fn default() -> Self {
+        MatchSettings {
+            board_width: 51,
+            board_height: 33,
+            area_width: None,
+            area_margin: None,
+            rule: Rule::default(),
+            grid: Grid::default(),
+            topology: Topology::default(),
+            hit_model: HitModel::default(),
+            max_hp: 50,
+            damage_per_hit: 10,
+            max_energy: 40,
+            energy_per_second: 2.0,
+            cell_cost: 1,
+            commit_cooldown: 1.0,
+            time_limit: None,
+            generation_delay: GENERATION_CALCULATION_DELAY,
+            history_length: 0,
+            stalemate_window: 400,
+            threads: 1
+        }
+    }
+}
+
+impl MatchSettings {
+    pub fn parse(text: &str) -> Result<MatchSettings, SettingsError> {
+        let mut settings = MatchSettings::default();
+        let mut rule_set = false;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {continue}
+
+            let error = |message: String| SettingsError { line: Some(i + 1), message };
+            let (key, value) = match line.find('=') {
+                Some(index) => (line[..index].trim(), line[index + 1..].trim()),
+                None => return Err(error(format!("expected `key = value`, found `{}`", line)))
+            };
+            settings.set(key, value).map_err(error)?;
+            rule_set |= key == "rule";
+        }
+        if settings.grid == Grid::HEX && !rule_set {
+            settings.rule = Rule::HEXLIFE;
+        }
+
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "board_width"  => self.board_width = parse_number(key, value)?,
+            "board_height" => self.board_height = parse_number(key, value)?,
+            "area_width"   => self.area_width = Some(parse_number(key, value)?),
+            "area_margin"  => self.area_margin = Some(parse_number(key, value)?),
+            "rule"         => self.rule = Rule::parse(value)?,
+            "grid"         => self.grid = Grid::parse(value)?,
+            "topology"     => self.topology = Topology::parse(value)?,
+            "hit_run_length" => self.hit_model.run_length = parse_number(key, value)?,
+            "scaled_damage"  => self.hit_model.scaled = parse_bool(key, value)?,
+            "max_hp"         => self.max_hp = parse_number(key, value)?,
+            "damage_per_hit" => self.damage_per_hit = parse_number(key, value)?,
+            "max_energy"     => self.max_energy = parse_number(key, value)?,
+            "energy_per_second" => self.energy_per_second = parse_decimal(key, value)?,
+            "cell_cost"      => self.cell_cost = parse_number(key, value)?,
+            "commit_cooldown" => self.commit_cooldown = parse_decimal(key, value)?,
+            "time_limit"     => self.time_limit = match parse_number(key, value)? {
+                0 => None,
+                seconds => Some(seconds as f32)
+            },
+            "generation_delay" => self.generation_delay = parse_seconds(key, value)?,
+            "history_length" => self.history_length = parse_number(key, value)?,
+            "stalemate_window" => self.stalemate_window = parse_number(key, value)?,
+            "threads"        => self.threads = parse_number(key, value)?,
+            _ => return Err(format!("unknown setting `{}`", key))
+        }
+
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        let error = |message: String| Err(SettingsError { line: None, message });
+        if self.board_width < 4 || self.board_height < 3 {
+            return error(format!("the board must be at least 4x3 blocks, got {}x{}", self.board_width, self.board_height))
+        }
+        if self.grid == Grid::HEX && self.topology.wraps_vertically() && !self.board_height.is_multiple_of(2) {
+            return error(format!("a hex grid can only wrap around with an even board height, got {}", self.board_height))
+        }
+        if self.area_width() == 0 {
+            return error("the selectable areas must be at least 1 block wide".to_string())
+        }
+        if self.hit_model.run_length == 0 || self.hit_model.run_length > self.board_height {
+            return error(format!("the hit run length must be between 1 and the board height ({})", self.board_height))
+        }
+        if self.max_hp == 0 || self.damage_per_hit == 0 {
+            return error("the maximum HP and the damage per hit must be at least 1".to_string())
+        }
+        if self.cell_cost > self.max_energy {
+            return error(format!("a cell costs {} energy, more than the maximum energy of {}", self.cell_cost, self.max_energy))
+        }
+        if self.area_margin() + self.area_width() > self.board_width / 2 {
+            return error(format!("the selectable areas do not fit in half of a {} blocks wide board", self.board_width))
+        }
+
+        Ok(())
+    }
+
+    // Ticks between two generations at the start of a match.
+    pub fn generation_ticks(&self) -> u64 {
+        ((self.generation_delay * TICKS_PER_SECOND as f32).round() as u64).max(1)
+    }
+
+    pub fn thread_count(&self) -> usize {
+        match self.threads {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            threads => threads
+        }
+    }
+
+    pub fn area_margin(&self) -> usize {
+        self.area_margin.unwrap_or((self.board_width / 8).saturating_sub(3))
+    }
+
+    pub fn area_width(&self) -> usize {
+        let max_width = (self.board_width / 2).saturating_sub(self.area_margin());
+        self.area_width.unwrap_or((self.board_width / 4 + 3).min(max_width))
+    }
+
+    // (left, right, upper, lower) bounds of the area a player can select squares in, inclusive.
+    // The two areas mirror each other around the middle of the board.
+    pub fn player_area(&self, player_num: &PlayerNum) -> (usize, usize, usize, usize) {
+        let (upper, lower) = (1, self.board_height - 2);
+        let left = self.area_margin();
+        let right = left + self.area_width() - 1;
+        match player_num {
+            PlayerNum::ONE => (left, right, upper, lower),
+            PlayerNum::TWO => (self.board_width - 1 - right, self.board_width - 1 - left, upper, lower)
+        }
+    }
+}
+
+fn parse_number(key: &str, value: &str) -> Result<usize, String> {
+    value.parse().map_err(|_| format!("`{}` must be a positive whole number, found `{}`", key, value))
+}
+
+fn parse_seconds(key: &str, value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(seconds) if seconds.is_finite() && seconds > 0.0 => Ok(seconds),
+        _ => Err(format!("`{}` must be a positive number of seconds, found `{}`", key, value))
+    }
+}
+
+fn parse_decimal(key: &str, value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(number) if number.is_finite() && number >= 0.0 => Ok(number),
+        _ => Err(format!("`{}` must be a positive number, found `{}`", key, value))
+    }
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "yes" | "on" => Ok(true),
+        "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("`{}` must be true or false, found `{}`", key, value))
+    }
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "settings line {}: {}", line, self.message),
+            None => write!(f, "invalid settings: {}", self.message)
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_areas_are_the_original_ones() {
+        let settings = MatchSettings::default();
+        assert_eq!(settings.player_area(&PlayerNum::ONE), (3, 17, 1, 31));
+        assert_eq!(settings.player_area(&PlayerNum::TWO), (33, 47, 1, 31));
+        assert_eq!(MatchSettings::parse("# nothing set\n\n"), Ok(settings));
+    }
+
+    #[test]
+    fn mistakes_name_the_setting() {
+        let error = MatchSettings::parse("board_width = 60\nspeed = 2").unwrap_err();
+        assert_eq!(error.line, Some(2));
+        assert!(error.message.contains("`speed`"), "{}", error);
+
+        for line in ["board_height = tall", "max_hp = -5", "energy_per_second = fast", "generation_delay = 0"].iter() {
+            let key = line.split(' ').next().unwrap();
+            let error = MatchSettings::parse(line).unwrap_err();
+            assert_eq!(error.line, Some(1));
+            assert!(error.message.contains(&format!("`{}`", key)), "{}", error);
+        }
+    }
+
+    #[test]
+    fn areas_must_fit_their_half_of_the_board() {
+        for text in ["area_width = 24", "area_margin = 20\narea_width = 10", "area_margin = 25", "area_width = 0", "board_width = 20\narea_margin = 10"].iter() {
+            let error = MatchSettings::parse(text).unwrap_err();
+            assert_eq!(error.line, None, "{}", text);
+        }
+        assert!(MatchSettings::parse("area_width = 22\narea_margin = 3").is_ok());
+    }
+
+    #[test]
+    fn the_areas_mirror_each_other() {
+        for text in ["", "board_width = 80\nboard_height = 40", "board_width = 30\narea_width = 5\narea_margin = 0", "board_width = 9"].iter() {
+            let settings = MatchSettings::parse(text).unwrap();
+            let (left, right, upper, lower) = settings.player_area(&PlayerNum::ONE);
+            let last_x = settings.board_width - 1;
+            assert_eq!(settings.player_area(&PlayerNum::TWO), (last_x - right, last_x - left, upper, lower), "{}", text);
+            assert!(right < last_x - right, "{}", text);
+        }
+    }
+}
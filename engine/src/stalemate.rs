@@ -0,0 +1,107 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+
+use crate::board::Board;
+
+// Notices when a match has stopped going anywhere: for `window` generations in a row the board repeated
+// one of the `window` boards before it, as still lifes and oscillators do, and nobody was hit.
+#[derive(Debug, Clone)]
+pub struct Stalemate {
+    window: usize,
+    // Hashes of the most recent boards, oldest first.
+    recent: VecDeque<u64>,
+    stalled_generations: usize
+}
+
+impl Stalemate {
+    // A `window` of 0 never detects a stalemate.
+    pub fn new(window: usize) -> Stalemate {
+        Stalemate {
+            window,
+            recent: VecDeque::with_capacity(window.min(1024)),
+            stalled_generations: 0
+        }
+    }
+
+    // Records the board of a new generation and whether anyone was hit on the way to it.
+    // Returns whether the match is in a stalemate.
+    pub fn record(&mut self, board: &Board, hit: bool) -> bool {
+        if self.window == 0 {return false}
+
+        let mut hasher = DefaultHasher::new();
+        board.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        if !hit && self.recent.contains(&hash) {
+            self.stalled_generations += 1;
+        } else {
+            self.stalled_generations = 0;
+        }
+        if self.recent.len() == self.window {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(hash);
+
+        self.stalled_generations >= self.window
+    }
+
+    // Forgets every board, for when the board changes other than by calculating a generation.
+    pub fn clear(&mut self) {
+        self.recent.clear();
+        self.stalled_generations = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::PlayerNum;
+
+    fn block() -> Board {
+        let mut board = Board::new(6, 6);
+        for (x, y) in [(2, 2), (3, 2), (2, 3), (3, 3)].iter() {
+            board.set(*x, *y, Some(PlayerNum::ONE));
+        }
+        board
+    }
+
+    #[test]
+    fn still_lifes_stall_after_the_window() {
+        let mut stalemate = Stalemate::new(3);
+        let stalled: Vec<bool> = (0..5).map(|_| stalemate.record(&block(), false)).collect();
+        assert_eq!(stalled, vec![false, false, false, true, true]);
+    }
+
+    #[test]
+    fn oscillators_stall_too() {
+        let mut blinker = [Board::new(5, 5), Board::new(5, 5)];
+        for i in 1..4 {
+            blinker[0].set(i, 2, Some(PlayerNum::TWO));
+            blinker[1].set(2, i, Some(PlayerNum::TWO));
+        }
+        let mut stalemate = Stalemate::new(2);
+        let stalled: Vec<bool> = (0..5).map(|i| stalemate.record(&blinker[i % 2], false)).collect();
+        assert_eq!(stalled, vec![false, false, false, true, true]);
+    }
+
+    #[test]
+    fn hits_and_clearing_start_over() {
+        let mut stalemate = Stalemate::new(2);
+        stalemate.record(&block(), false);
+        stalemate.record(&block(), false);
+        assert!(!stalemate.record(&block(), true));
+        assert!(!stalemate.record(&block(), false));
+        assert!(stalemate.record(&block(), false));
+
+        stalemate.clear();
+        let stalled: Vec<bool> = (0..3).map(|_| stalemate.record(&block(), false)).collect();
+        assert_eq!(stalled, vec![false, false, true]);
+    }
+
+    #[test]
+    fn a_window_of_zero_never_stalls() {
+        let mut stalemate = Stalemate::new(0);
+        assert!((0..10).all(|_| !stalemate.record(&block(), false)));
+    }
+}
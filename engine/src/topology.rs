@@ -0,0 +1,206 @@
+use std::fmt;
+
+use crate::board::NEIGHBOUR_OFFSETS;
+
+// Offsets of the 6 neighbours of a cell on a hex grid, clockwise starting from the upper right one.
+// Odd rows are shifted half a cell to the right, so the offsets depend on the parity of the row.
+const HEX_EVEN_ROW_OFFSETS: [(isize, isize); 6] = [(0,-1), (1,0), (0,1), (-1,1), (-1,0), (-1,-1)];
+const HEX_ODD_ROW_OFFSETS: [(isize, isize); 6] = [(1,-1), (1,0), (1,1), (0,1), (-1,0), (0,-1)];
+
+// The shape of the cells.
+// SQUARE: every cell has 8 neighbours.
+// HEX: every cell has 6 neighbours: 2 in its own row and 2 in each of the rows above and below it.
+// The cells of a column still touch from one row to the next, so the goal edges work the same on both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Grid {
+    #[default]
+    SQUARE,
+    HEX
+}
+
+impl Grid {
+    pub fn parse(text: &str) -> Result<Grid, String> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "square" => Ok(Grid::SQUARE),
+            "hex"    => Ok(Grid::HEX),
+            _ => Err(format!("`{}` is not a grid, expected square or hex", text))
+        }
+    }
+
+    // Offsets of the neighbours of a cell in row `y`, clockwise.
+    pub fn neighbour_offsets(&self, y: usize) -> &'static [(isize, isize)] {
+        match self {
+            Grid::SQUARE => &NEIGHBOUR_OFFSETS,
+            Grid::HEX if y.is_multiple_of(2) => &HEX_EVEN_ROW_OFFSETS,
+            Grid::HEX => &HEX_ODD_ROW_OFFSETS
+        }
+    }
+
+    // How many columns a diagonal step from row `y` moves, towards the right when `right` is set.
+    // On a hex grid one of the two diagonals of every row stays in the same column.
+    pub fn diagonal_dx(&self, y: usize, right: bool) -> isize {
+        match (self, y.is_multiple_of(2), right) {
+            (Grid::SQUARE, _, true) => 1,
+            (Grid::SQUARE, _, false) => -1,
+            (Grid::HEX, true, true) | (Grid::HEX, false, false) => 0,
+            (Grid::HEX, true, false) => -1,
+            (Grid::HEX, false, true) => 1
+        }
+    }
+
+    // The cell (x, y) moves to when (0, 0) moves to (dx, dy). On a hex grid moving by an odd number of rows
+    // shifts the odd and the even rows differently, so shapes keep their form whatever row they end up on.
+    pub fn translate(&self, (x, y): (isize, isize), (dx, dy): (isize, isize)) -> (isize, isize) {
+        match self {
+            Grid::SQUARE => (x + dx, y + dy),
+            Grid::HEX => {
+                let ((q, r), (dq, dr)) = (to_axial(x, y), to_axial(dx, dy));
+                from_axial(q + dq, r + dr)
+            }
+        }
+    }
+
+    // The move that translates `from` to `to`.
+    pub fn displacement(&self, from: (isize, isize), to: (isize, isize)) -> (isize, isize) {
+        match self {
+            Grid::SQUARE => (to.0 - from.0, to.1 - from.1),
+            Grid::HEX => {
+                let ((from_q, from_r), (to_q, to_r)) = (to_axial(from.0, from.1), to_axial(to.0, to.1));
+                from_axial(to_q - from_q, to_r - from_r)
+            }
+        }
+    }
+
+    // How many of the turns made by `rotate` make a full turn: quarter turns on squares, sixth turns on hexes.
+    pub fn turns(&self) -> u8 {
+        match self {
+            Grid::SQUARE => 4,
+            Grid::HEX => 6
+        }
+    }
+
+    // The cell (x, y) moves to when turned clockwise around `pivot` by one of the turns above.
+    pub fn rotate(&self, (x, y): (isize, isize), pivot: (isize, isize)) -> (isize, isize) {
+        match self {
+            Grid::SQUARE => (pivot.0 - (y - pivot.1), pivot.1 + (x - pivot.0)),
+            Grid::HEX => {
+                let ((q, r), (pivot_q, pivot_r)) = (to_axial(x, y), to_axial(pivot.0, pivot.1));
+                let (q, r) = (q - pivot_q, r - pivot_r);
+                from_axial(pivot_q - r, pivot_r + q + r)
+            }
+        }
+    }
+
+    // The cell (x, y) moves to when mirrored left to right around column 0, or upside down around row 0.
+    // On a hex grid the odd rows are shifted half a cell, so their cells land one column further to the left.
+    pub fn mirror(&self, (x, y): (isize, isize), horizontally: bool) -> (isize, isize) {
+        match (self, horizontally) {
+            (Grid::SQUARE, true) => (-x, y),
+            (Grid::HEX, true) => (-x - (y & 1), y),
+            (_, false) => (x, -y)
+        }
+    }
+}
+
+// Hex cells in (column, row) are (q, r) in axial coordinates, where the q axis runs along the rows
+// and the r axis down and to the right, so moving in any of the 6 directions is adding the same (dq, dr) anywhere.
+pub(crate) fn to_axial(x: isize, y: isize) -> (isize, isize) {
+    (x - (y - (y & 1)) / 2, y)
+}
+
+pub(crate) fn from_axial(q: isize, r: isize) -> (isize, isize) {
+    (q + (r - (r & 1)) / 2, r)
+}
+
+// Hex cells in axial coordinates, moved so the smallest q and r are 0, in order. Two sets of cells have the
+// same shape when one can be moved onto the other.
+#[cfg(test)]
+pub(crate) fn axial_shape(cells: &[crate::point::Point2u]) -> Vec<(isize, isize)> {
+    let cells: Vec<(isize, isize)> = cells.iter().map(|p| to_axial(p.x as isize, p.y as isize)).collect();
+    let (min_q, min_r) = (cells.iter().map(|p| p.0).min().unwrap_or(0), cells.iter().map(|p| p.1).min().unwrap_or(0));
+    let mut shape: Vec<(isize, isize)> = cells.iter().map(|(q, r)| (q - min_q, r - min_r)).collect();
+    shape.sort();
+    shape
+}
+
+// How the edges of the board connect.
+// BOUNDED: every edge is a dead border.
+// CYLINDER: the top and the bottom edges wrap around, the left and right edges stay the players' goal edges.
+// TORUS: every edge wraps around. There are no goal edges, so nobody takes damage; meant for sandbox play.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Topology {
+    #[default]
+    BOUNDED,
+    CYLINDER,
+    TORUS
+}
+
+impl Topology {
+    pub fn parse(text: &str) -> Result<Topology, String> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "bounded"  => Ok(Topology::BOUNDED),
+            "cylinder" => Ok(Topology::CYLINDER),
+            "torus"    => Ok(Topology::TORUS),
+            _ => Err(format!("`{}` is not a topology, expected bounded, cylinder or torus", text))
+        }
+    }
+
+    pub fn wraps_vertically(&self) -> bool {
+        *self != Topology::BOUNDED
+    }
+
+    pub fn wraps_horizontally(&self) -> bool {
+        *self == Topology::TORUS
+    }
+
+    pub fn has_goal_edges(&self) -> bool {
+        *self != Topology::TORUS
+    }
+
+    // The column `dx` away from `x` on a board `width` wide, if it is on the board.
+    pub fn neighbour_column(&self, x: usize, dx: isize, width: usize) -> Option<usize> {
+        step(x, dx, width, self.wraps_horizontally())
+    }
+
+    // The row `dy` away from `y` on a board `height` high, if it is on the board.
+    pub fn neighbour_row(&self, y: usize, dy: isize, height: usize) -> Option<usize> {
+        step(y, dy, height, self.wraps_vertically())
+    }
+
+    // The cell (dx, dy) away from (x, y) on a `width` x `height` board, if it is on the board.
+    pub fn neighbour(&self, x: usize, y: usize, dx: isize, dy: isize, width: usize, height: usize) -> Option<(usize, usize)> {
+        Some((self.neighbour_column(x, dx, width)?, self.neighbour_row(y, dy, height)?))
+    }
+}
+
+fn step(position: usize, delta: isize, size: usize, wraps: bool) -> Option<usize> {
+    let moved = position as isize + delta;
+    if wraps {
+        Some(moved.rem_euclid(size as isize) as usize)
+    } else if moved < 0 || moved >= size as isize {
+        None
+    } else {
+        Some(moved as usize)
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Grid::SQUARE => "square",
+            Grid::HEX    => "hex"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Topology::BOUNDED  => "bounded",
+            Topology::CYLINDER => "cylinder",
+            Topology::TORUS    => "torus"
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -0,0 +1,140 @@
+use crate::board::{calculate_next_generation_parallel, Board, Cell};
+use crate::damage::{check_universe_for_damage, HitModel, Hits};
+use crate::rules::Rule;
+use crate::topology::Topology;
+
+// A board that knows how to evolve itself under a rule.
+// Lets bots and tools pick the backend that suits them: `BitUniverse` is fast for stepping
+// one generation at a time, `HashLife` for jumping far ahead on big or repetitive boards.
+pub trait Universe {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn topology(&self) -> Topology;
+    fn get(&self, x: usize, y: usize) -> Cell;
+    fn set(&mut self, x: usize, y: usize, cell: Cell);
+    // How many generations have been calculated.
+    fn generation(&self) -> u64;
+    fn step(&mut self);
+    fn advance(&mut self, generations: u64);
+    fn to_board(&self) -> Board;
+}
+
+// Advances the universe, checking both edges for hits before every generation the same way a match does.
+// Returns the generation and the hits of every generation someone was hit in.
+// Live cells spread at most one column a generation, so while none are near the edges the universe jumps ahead
+// in one `advance` to the first generation they could reach an edge in. From there, and for as long as cells stay
+// on the edges, it falls back to single steps.
+pub fn advance_with_damage(universe: &mut dyn Universe, generations: u64, model: &HitModel) -> Vec<(u64, Hits)> {
+    let mut hits = Vec::new();
+    if !universe.topology().has_goal_edges() {
+        universe.advance(generations);
+        return hits
+    }
+
+    let mut left = generations;
+    while left > 0 {
+        let quiet_generations = generations_before_an_edge(universe, left);
+        if quiet_generations > 0 {
+            universe.advance(quiet_generations);
+            left -= quiet_generations;
+            continue
+        }
+
+        let damage = check_universe_for_damage(universe, model);
+        if damage.0.is_some() || damage.1.is_some() {
+            hits.push((universe.generation(), damage));
+        }
+        universe.step();
+        left -= 1;
+    }
+
+    hits
+}
+
+// How many generations, up to `limit`, pass before a live cell could be on the left or right edge.
+// That is how many columns the live cells closest to an edge are away from it, as no rule births cells from nothing.
+fn generations_before_an_edge(universe: &dyn Universe, limit: u64) -> u64 {
+    let (width, height) = (universe.width(), universe.height());
+    for distance in 0..width.div_ceil(2) {
+        if distance as u64 >= limit {break}
+        if (0..height).any(|y| universe.get(distance, y).is_some() || universe.get(width - 1 - distance, y).is_some()) {
+            return distance as u64
+        }
+    }
+
+    limit
+}
+
+// The bit-packed `Board`, double buffered.
+#[derive(Debug, Clone)]
+pub struct BitUniverse {
+    board: Board,
+    next_board: Board,
+    rule: Rule,
+    threads: usize,
+    generation: u64
+}
+
+impl BitUniverse {
+    pub fn new(board: Board, rule: Rule) -> BitUniverse {
+        BitUniverse {
+            next_board: Board::with_shape(board.width(), board.height(), board.grid(), board.topology()),
+            board,
+            rule,
+            threads: 1,
+            generation: 0
+        }
+    }
+
+    // Calculates every generation on up to `threads` threads.
+    pub fn with_threads(mut self, threads: usize) -> BitUniverse {
+        self.threads = threads;
+        self
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+}
+
+impl Universe for BitUniverse {
+    fn width(&self) -> usize {
+        self.board.width()
+    }
+
+    fn height(&self) -> usize {
+        self.board.height()
+    }
+
+    fn topology(&self) -> Topology {
+        self.board.topology()
+    }
+
+    fn get(&self, x: usize, y: usize) -> Cell {
+        self.board.get(x, y)
+    }
+
+    fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        self.board.set(x, y, cell)
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn step(&mut self) {
+        calculate_next_generation_parallel(&self.board, &mut self.next_board, &self.rule, self.threads);
+        std::mem::swap(&mut self.board, &mut self.next_board);
+        self.generation += 1;
+    }
+
+    fn advance(&mut self, generations: u64) {
+        for _ in 0..generations {
+            self.step();
+        }
+    }
+
+    fn to_board(&self) -> Board {
+        self.board.clone()
+    }
+}
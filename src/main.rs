@@ -1,6 +1,8 @@
 #![windows_subsystem = "windows"]
 
-use std::time::Instant;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use ggez::conf::{WindowMode, WindowSetup};
 use ggez::{Context, ContextBuilder, GameResult, timer};
@@ -9,124 +11,153 @@ use ggez::event::{self, EventHandler, KeyCode, KeyMods};
 
 use lazy_static::lazy_static;
 
+use engine::{Board, Direction, Game, GameState, Grid, MatchSettings, Outcome, Pattern, Player, PlayerNum};
+
 type Point2f = ggez::mint::Point2<f32>;
-type Point2u = ggez::mint::Point2<usize>;
-type BoardType = [[bool; HORIZONTAL_BLOCKS]; VERTICAL_BLOCKS];
 
-// BLOCK_SIZE is a common perfect divisor of INNER_X and WINDOW_Y.
-// These hardcoded values should only be changed if the above condition is met.
+// The board is scaled to fit INNER_X x WINDOW_Y, whatever its dimensions are.
 const HP_BAR_WIDTH : f32 = 20.0;
 const INNER_X      : f32 = 1479.0;
 const WINDOW_X     : f32 = INNER_X + 2.0 * HP_BAR_WIDTH;
 const WINDOW_Y     : f32 = 957.0;
-const BLOCK_SIZE   : f32 = 29.0;
-const HORIZONTAL_BLOCKS : usize = (INNER_X / BLOCK_SIZE) as usize;
-const VERTICAL_BLOCKS   : usize = (WINDOW_Y / BLOCK_SIZE) as usize;
 
-const AREA_1_X : f32 = ((HORIZONTAL_BLOCKS/8) as f32 - 3.0)* BLOCK_SIZE + HP_BAR_WIDTH;
-const AREA_2_X_OFFSET : f32 = if HORIZONTAL_BLOCKS % 2 == 0 {1.0} else {2.0};
-const MIDDLE_POINT:f32 = (HORIZONTAL_BLOCKS/2) as f32 + AREA_2_X_OFFSET;
-const AREA_2_X : f32 = ((HORIZONTAL_BLOCKS/8) as f32 + MIDDLE_POINT) * BLOCK_SIZE + HP_BAR_WIDTH;
-const AREA_WIDTH  : f32 = ((HORIZONTAL_BLOCKS/4) as f32 + 3.0) * BLOCK_SIZE;
-const AREA_LENGTH : f32 = (VERTICAL_BLOCKS-2) as f32 * BLOCK_SIZE;
+const SETTINGS_FILE_NAME: &str = "settings.cfg";
+// Holds .rle and .cells pattern files, added to the pattern library after the built-in patterns.
+const PATTERNS_DIR_NAME: &str = "patterns";
 
-const GENERATION_CALCULATION_DELAY: f32 = 0.15;
+// How long a rejected commit or what an export did is shown, in seconds.
+const FEEDBACK_DURATION: f32 = 2.0;
+// How long the problems with settings.cfg and the pattern files are shown, in seconds.
+const LOAD_PROBLEMS_DURATION: f32 = 10.0;
 
 
 lazy_static! {
+    // from full to no HP
     static ref LIFE_COLORS:[Color; 6] = [Color::from_rgb(105, 212, 76), Color::from_rgb(151, 212, 76), Color::from_rgb(203, 212, 76),
-                                         Color::from_rgb(219, 190, 75), Color::from_rgb(219, 157, 75), Color::from_rgb(217, 80, 56)];
+                                                            Color::from_rgb(219, 190, 75), Color::from_rgb(219, 157, 75), Color::from_rgb(217, 80, 56)];
 
     static ref STROKE_MODE_1: graphics::DrawMode = graphics::DrawMode::Stroke(StrokeOptions::default().with_line_width(1.0));
     static ref STROKE_MODE_2: graphics::DrawMode = graphics::DrawMode::Stroke(StrokeOptions::default().with_line_width(2.0));
     static ref FILL_MODE    : graphics::DrawMode = graphics::DrawMode::Fill(FillOptions::default());
 }
 
-#[macro_use]
-macro_rules! pointu {
-    ($x:expr,$y:expr) => {
-        Point2u{x:$x,y:$y}
-    }
-}
-#[macro_use]
 macro_rules! pointf {
     ($x:expr,$y:expr) => {
         Point2f{x:$x,y:$y}
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
-enum PlayerNum {
-    ONE,
-    TWO
+// Where the board is drawn on the window: blocks as big as fit, centered between the HP bars.
+// On a hex grid the blocks are pointy-topped hexagons `block_size` wide, with every odd row shifted half a block to the right.
+struct Layout {
+    grid: Grid,
+    block_size: f32,
+    // Height of a block, and the distance between the tops of two rows. Both are the block size for square blocks.
+    block_height: f32,
+    row_height: f32,
+    origin_x: f32,
+    origin_y: f32
 }
 
-#[derive(Debug)]
-enum Direction {
-    UP,
-    RIGHT,
-    LEFT,
-    DOWN
-}
+impl Layout {
+    fn new(board: &Board) -> Layout {
+        let (width, height) = (board.width() as f32, board.height() as f32);
+        // the size of the board in blocks, with blocks 1 wide
+        let (hex_height, hex_row_height) = (2.0 / 3f32.sqrt(), 3f32.sqrt() / 2.0);
+        let (board_width, board_height) = match board.grid() {
+            Grid::SQUARE => (width, height),
+            Grid::HEX => (width + 0.5, (height - 1.0) * hex_row_height + hex_height)
+        };
 
-#[derive(Debug,PartialEq)]
-enum GameState {
-    PLAYING,
-    PAUSE_MENU,
-    WINNER_SCREEN
-}
+        let block_size = (INNER_X / board_width).min(WINDOW_Y / board_height).floor().max(1.0);
+        let (block_height, row_height) = match board.grid() {
+            Grid::SQUARE => (block_size, block_size),
+            Grid::HEX => (block_size * hex_height, block_size * hex_row_height)
+        };
+        Layout {
+            grid: board.grid(),
+            block_size,
+            block_height,
+            row_height,
+            origin_x: HP_BAR_WIDTH + (INNER_X - block_size * board_width) / 2.0,
+            origin_y: (WINDOW_Y - block_size * board_height) / 2.0
+        }
+    }
 
-#[derive(Debug)]
-struct Player {
-    pub player_num: PlayerNum,
-    pub movement_cooldown_time: f32,
-    pub life_color_index: usize,
-    pub hovering_square: Point2u,
-    pub selected_squares: Vec<Point2u>,
-    _x_left_bound: usize,
-    _x_right_bound: usize,
-    _y_upper_bound: usize,
-    _y_lower_bound: usize,
-}
+    // The rectangle covering the blocks from (x, y) up to (x + width - 1, y + height - 1).
+    // On a hex grid it also covers the half block the odd rows are shifted by.
+    fn blocks(&self, x: usize, y: usize, width: usize, height: usize) -> Rect {
+        let extra_width = if self.grid == Grid::HEX {self.block_size / 2.0} else {0.0};
+        Rect::new(self.origin_x + x as f32 * self.block_size, self.origin_y + y as f32 * self.row_height,
+                  width as f32 * self.block_size + extra_width, (height - 1) as f32 * self.row_height + self.block_height)
+    }
+
+    // How far right the blocks of row `y` are shifted.
+    fn shift(&self, y: usize) -> f32 {
+        if self.grid == Grid::HEX && y % 2 == 1 {self.block_size / 2.0} else {0.0}
+    }
+
+    // Adds block (x, y) to the mesh: a square or a hexagon, depending on the grid.
+    fn block(&self, mb: &mut MeshBuilder, mode: graphics::DrawMode, x: usize, y: usize, color: Color) -> GameResult {
+        match self.grid {
+            Grid::SQUARE => {
+                mb.rectangle(mode, self.blocks(x, y, 1, 1), color)?;
+            },
+            Grid::HEX => {
+                let center_x = self.origin_x + (x as f32 + 0.5) * self.block_size + self.shift(y);
+                let center_y = self.origin_y + y as f32 * self.row_height + self.block_height / 2.0;
+                let radius = self.block_height / 2.0;
+                let corners: Vec<Point2f> = (0..6).map(|i| {
+                    let angle = (30.0 + 60.0 * i as f32).to_radians();
+                    pointf![center_x + radius * angle.cos(), center_y + radius * angle.sin()]
+                }).collect();
+                mb.polygon(mode, &corners, color)?;
+            }
+        }
 
+        Ok(())
+    }
+}
 
-#[derive(Debug)]
-struct Game {
-    state: GameState,
-    timer: f32,
+// The ggez frontend over the headless game in `engine`.
+struct App {
+    game: Game,
     last_update_time: Instant,
-    player1: Player,
-    player2: Player,
-    winner: Option<PlayerNum>,
-    board: BoardType
+    // Why the last commit or saved selection of player 1 and player 2 was rejected, and when.
+    feedback: [Option<(String, Instant)>; 2],
+    // What the last export, editor save or loading of the settings and patterns did, and until when it is shown.
+    message: Option<(String, Instant)>,
+    // The outcome last printed to stdout, so every finished match is printed once.
+    printed_outcome: Option<Outcome>
 }
 
 
-impl EventHandler<ggez::GameError> for Game {
+impl EventHandler<ggez::GameError> for App {
     fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
-        if self.state != GameState::PLAYING {return Ok(())}
-
         let elapsed = self.last_update_time.elapsed().as_secs_f32();
         self.last_update_time = Instant::now();
-        self.timer += elapsed;
-
-        if self.timer >= GENERATION_CALCULATION_DELAY {
-            self.timer = 0.0;
-            let (next_board, damage_in_each_player) = calculate_next_generation(&mut self.board);
-            self.board = next_board;
-            make_damage_calculations(self, damage_in_each_player);
+        self.game.update(elapsed);
+        if self.game.outcome != self.printed_outcome {
+            match self.game.outcome {
+                Some(Outcome::WIN(PlayerNum::ONE)) => println!("player 1 won"),
+                Some(Outcome::WIN(PlayerNum::TWO)) => println!("player 2 won"),
+                Some(Outcome::DRAW) => println!("draw"),
+                None => {}
+            }
+            self.printed_outcome = self.game.outcome;
         }
-        
+
         Ok(())
     }
     
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
         graphics::clear(ctx, Color::from_rgb(170,170,170));
 
-        match self.state {
-            GameState::PLAYING => draw_board(ctx, self)?,
-            GameState::PAUSE_MENU => draw_pause_menu(ctx)?,
-            GameState::WINNER_SCREEN => draw_winner_screen(ctx, self)?
+        match self.game.state {
+            GameState::PLAYING | GameState::FROZEN => draw_board(ctx, self)?,
+            GameState::EDITOR => draw_editor(ctx, self)?,
+            GameState::PAUSE_MENU => draw_pause_menu(ctx, self)?,
+            GameState::WINNER_SCREEN => draw_winner_screen(ctx, &self.game)?
         }
         
         graphics::present(ctx)?;
@@ -135,158 +166,345 @@ impl EventHandler<ggez::GameError> for Game {
     }
 
     fn key_down_event(&mut self, ctx: &mut Context, key: KeyCode, mods: KeyMods, repeat: bool) {
+        if self.game.state == GameState::EDITOR {
+            editor_key_down(self, key, mods, repeat);
+            return
+        }
         if repeat {return}
-        
+
+        let game = &mut self.game;
+        let feedback = &mut self.feedback;
+        let message = &mut self.message;
         match key {
             KeyCode::Escape => {
                 ggez::event::quit(ctx)
             },
             KeyCode::P => {
-                if self.state == GameState::PLAYING {
-                    self.state = GameState::PAUSE_MENU 
-                } else if self.state == GameState::PAUSE_MENU {
-                    self.state = GameState::PLAYING 
+                if game.state == GameState::PLAYING {
+                    game.state = GameState::PAUSE_MENU 
+                } else if game.state == GameState::PAUSE_MENU {
+                    game.state = GameState::PLAYING 
                 }
             },
             KeyCode::R => { 
-                if self.state == GameState::PAUSE_MENU {return}
+                if game.state == GameState::PAUSE_MENU {return}
 
-                self.reset();
+                let (settings, patterns, problems) = load_match_files();
+                game.settings = settings;
+                game.patterns = patterns;
+                *message = problems.map(|problems| timed_message(problems, LOAD_PROBLEMS_DURATION));
+                game.reset();
+            },
+            KeyCode::F => {
+                if game.state == GameState::PLAYING {
+                    game.state = GameState::FROZEN
+                } else if game.state == GameState::FROZEN {
+                    game.state = GameState::PLAYING
+                }
+            },
+            KeyCode::N if game.state == GameState::FROZEN => {
+                game.step_generation()
+            },
+            KeyCode::LBracket => {
+                game.rewind()
+            },
+            KeyCode::RBracket => {
+                game.fast_forward()
+            },
+            KeyCode::Minus => {
+                game.slow_down()
+            },
+            KeyCode::Equals => {
+                game.speed_up()
+            },
+            KeyCode::Key0 => {
+                game.reset_speed()
+            },
+            KeyCode::Tab => {
+                game.open_editor()
+            },
+            KeyCode::F2 => {
+                let (width, height) = (game.board.width(), game.board.height());
+                *message = Some(timed_message(export_region(game, "board", (0, 0, width, height)), FEEDBACK_DURATION));
+            },
+            KeyCode::F3 | KeyCode::F4 => {
+                let (player, name) = if key == KeyCode::F3 {(&game.player1, "player-1-area")} else {(&game.player2, "player-2-area")};
+                let (left, right, upper, lower) = player.area_bounds();
+                *message = Some(timed_message(export_region(game, name, (left, upper, right - left + 1, lower - upper + 1)), FEEDBACK_DURATION));
             },
             KeyCode::B => { 
-                if self.state == GameState::WINNER_SCREEN {
-                    self.state = GameState::PLAYING
-                } else if self.state == GameState::PLAYING {
-                    self.state = GameState::WINNER_SCREEN 
+                if game.state == GameState::WINNER_SCREEN {
+                    game.state = GameState::PLAYING
+                } else if game.state == GameState::PLAYING {
+                    game.state = GameState::WINNER_SCREEN 
                 }
             },
             // Player1
             KeyCode::W => {
                 let amount = if mods.contains(KeyMods::ALT) {3} else {1};
-                self.player1.move_hover(Direction::UP, amount)
+                game.player1.move_hover(Direction::UP, amount)
             },
             KeyCode::D => {
                 let amount = if mods.contains(KeyMods::ALT) {3} else {1};
-                self.player1.move_hover(Direction::RIGHT, amount)
+                game.player1.move_hover(Direction::RIGHT, amount)
             },
             KeyCode::S => {
                 let amount = if mods.contains(KeyMods::ALT) {3} else {1};
-                self.player1.move_hover(Direction::DOWN, amount)
+                game.player1.move_hover(Direction::DOWN, amount)
             },
             KeyCode::A => {
                 let amount = if mods.contains(KeyMods::ALT) {3} else {1};
-                self.player1.move_hover(Direction::LEFT, amount)
+                game.player1.move_hover(Direction::LEFT, amount)
+            },
+            KeyCode::Q => {
+                let amount = if mods.contains(KeyMods::ALT) {3} else {1};
+                game.player1.move_hover(Direction::UP_LEFT, amount)
+            },
+            KeyCode::E => {
+                let amount = if mods.contains(KeyMods::ALT) {3} else {1};
+                game.player1.move_hover(Direction::UP_RIGHT, amount)
+            },
+            KeyCode::Z => {
+                let amount = if mods.contains(KeyMods::ALT) {3} else {1};
+                game.player1.move_hover(Direction::DOWN_LEFT, amount)
+            },
+            KeyCode::X => {
+                let amount = if mods.contains(KeyMods::ALT) {3} else {1};
+                game.player1.move_hover(Direction::DOWN_RIGHT, amount)
             },
             KeyCode::C => {
-                let index = self.player1.selected_squares.iter().position(|x| *x == self.player1.hovering_square);
-                if let Some(i) = index {
-                    self.player1.selected_squares.remove(i);
-                } else {
-                    self.player1.selected_squares.push(self.player1.hovering_square);
-                }
+                game.player1.toggle_hovered_square()
+            },
+            KeyCode::H => {
+                game.player1.use_selection_tool()
+            },
+            KeyCode::U => {
+                game.player1.selection_tool = game.player1.selection_tool.next()
+            },
+            KeyCode::J => {
+                game.player1.clear_selection()
+            },
+            KeyCode::I => {
+                game.player1.rotate_selection()
+            },
+            KeyCode::O => {
+                game.player1.mirror_selection(true)
+            },
+            KeyCode::K => {
+                game.player1.mirror_selection(false)
+            },
+            KeyCode::L => {
+                game.player1.dragging_selection = !game.player1.dragging_selection
+            },
+            KeyCode::Key1 | KeyCode::Key2 | KeyCode::Key3 | KeyCode::Key4 | KeyCode::Key5 => {
+                let slot = key as usize - KeyCode::Key1 as usize;
+                let result = if mods.contains(KeyMods::ALT) {game.player1.save_selection(slot)} else {game.player1.recall_selection(slot)};
+                feedback[0] = result.err().map(|error| (error, Instant::now()));
+            },
+            KeyCode::V => {
+                game.stamp_pattern(&PlayerNum::ONE)
+            },
+            KeyCode::G => {
+                let step = if mods.contains(KeyMods::SHIFT) {-1} else {1};
+                game.cycle_pattern(&PlayerNum::ONE, step)
+            },
+            KeyCode::T => {
+                game.player1.orientation = game.player1.orientation.rotated(game.board.grid())
+            },
+            KeyCode::Y => {
+                game.player1.orientation = game.player1.orientation.flipped()
             },
             KeyCode::Space => {
-                for p in self.player1.selected_squares.iter() {
-                    self.board[p.y][p.x] = true;
-                }
-                self.player1.selected_squares.clear();
+                feedback[0] = game.commit_selected_squares(&PlayerNum::ONE).err().map(|error| (error, Instant::now()));
             },
             //Player2
             KeyCode::Up => {
                 let amount = if mods.contains(KeyMods::CTRL) {3} else {1};
-                self.player2.move_hover(Direction::UP, amount)
+                game.player2.move_hover(Direction::UP, amount)
             },
             KeyCode::Right => {
                 let amount = if mods.contains(KeyMods::CTRL) {3} else {1};
-                self.player2.move_hover(Direction::RIGHT, amount)
+                game.player2.move_hover(Direction::RIGHT, amount)
             },
             KeyCode::Down => {
                 let amount = if mods.contains(KeyMods::CTRL) {3} else {1};
-                self.player2.move_hover(Direction::DOWN, amount)
+                game.player2.move_hover(Direction::DOWN, amount)
             },
             KeyCode::Left => {
                 let amount = if mods.contains(KeyMods::CTRL) {3} else {1};
-                self.player2.move_hover(Direction::LEFT, amount)
+                game.player2.move_hover(Direction::LEFT, amount)
+            },
+            KeyCode::Home => {
+                let amount = if mods.contains(KeyMods::CTRL) {3} else {1};
+                game.player2.move_hover(Direction::UP_LEFT, amount)
+            },
+            KeyCode::PageUp => {
+                let amount = if mods.contains(KeyMods::CTRL) {3} else {1};
+                game.player2.move_hover(Direction::UP_RIGHT, amount)
+            },
+            KeyCode::End => {
+                let amount = if mods.contains(KeyMods::CTRL) {3} else {1};
+                game.player2.move_hover(Direction::DOWN_LEFT, amount)
+            },
+            KeyCode::PageDown => {
+                let amount = if mods.contains(KeyMods::CTRL) {3} else {1};
+                game.player2.move_hover(Direction::DOWN_RIGHT, amount)
             },
             KeyCode::RShift => {
-                let index = self.player2.selected_squares.iter().position(|x| *x == self.player2.hovering_square);
-                if let Some(i) = index {
-                    self.player2.selected_squares.remove(i);
-                } else {
-                    self.player2.selected_squares.push(self.player2.hovering_square);
-                }
+                game.player2.toggle_hovered_square()
+            },
+            KeyCode::Numpad0 => {
+                game.player2.use_selection_tool()
+            },
+            KeyCode::NumpadEnter => {
+                game.player2.selection_tool = game.player2.selection_tool.next()
+            },
+            KeyCode::NumpadDecimal => {
+                game.player2.clear_selection()
+            },
+            KeyCode::NumpadDivide => {
+                game.player2.rotate_selection()
+            },
+            KeyCode::NumpadMultiply => {
+                game.player2.mirror_selection(true)
+            },
+            KeyCode::NumpadSubtract => {
+                game.player2.mirror_selection(false)
+            },
+            KeyCode::NumpadAdd => {
+                game.player2.dragging_selection = !game.player2.dragging_selection
+            },
+            KeyCode::Numpad1 | KeyCode::Numpad2 | KeyCode::Numpad3 | KeyCode::Numpad4 | KeyCode::Numpad5 => {
+                let slot = key as usize - KeyCode::Numpad1 as usize;
+                let result = if mods.contains(KeyMods::CTRL) {game.player2.save_selection(slot)} else {game.player2.recall_selection(slot)};
+                feedback[1] = result.err().map(|error| (error, Instant::now()));
+            },
+            KeyCode::Slash => {
+                game.stamp_pattern(&PlayerNum::TWO)
+            },
+            KeyCode::Period => {
+                game.cycle_pattern(&PlayerNum::TWO, 1)
+            },
+            KeyCode::Comma => {
+                game.cycle_pattern(&PlayerNum::TWO, -1)
+            },
+            KeyCode::Semicolon => {
+                game.player2.orientation = game.player2.orientation.rotated(game.board.grid())
+            },
+            KeyCode::Apostrophe => {
+                game.player2.orientation = game.player2.orientation.flipped()
             },
             KeyCode::Return => {
-                for p in self.player2.selected_squares.iter() {
-                    self.board[p.y][p.x] = true;
-                }
-                self.player2.selected_squares.clear();
+                feedback[1] = game.commit_selected_squares(&PlayerNum::TWO).err().map(|error| (error, Instant::now()));
             },
             _ => ()
         }
     }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) {
+        if self.game.state == GameState::EDITOR {
+            self.game.editor.type_char(character)
+        }
+    }
 }
 
+// Keys while the pattern editor is open. Letters and digits go to the pattern name instead, through `text_input_event`.
+// Held arrows and Backspace repeat.
+fn editor_key_down(app: &mut App, key: KeyCode, mods: KeyMods, repeat: bool) {
+    if repeat && !matches!(key, KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right | KeyCode::Back) {return}
+    let amount = if mods.contains(KeyMods::CTRL) {3} else {1};
+    let editor = &mut app.game.editor;
+    match key {
+        KeyCode::Escape | KeyCode::Tab => app.game.close_editor(),
+        KeyCode::Up    => editor.move_cursor(0, -amount),
+        KeyCode::Down  => editor.move_cursor(0, amount),
+        KeyCode::Left  => editor.move_cursor(-amount, 0),
+        KeyCode::Right => editor.move_cursor(amount, 0),
+        KeyCode::Space => editor.toggle_cell(),
+        KeyCode::Delete => editor.clear(),
+        KeyCode::Back => {
+            editor.name.pop();
+        },
+        KeyCode::PageUp => editor.preview_generations += 1,
+        KeyCode::PageDown => editor.preview_generations = editor.preview_generations.saturating_sub(1),
+        KeyCode::Home => editor.preview_generations = 0,
+        KeyCode::Return => {
+            let result = editor.to_pattern(app.game.settings.rule).and_then(|pattern| {
+                let message = save_pattern(&pattern)?;
+                app.game.patterns.push(pattern);
+                Ok(message)
+            });
+            app.message = Some(timed_message(result.unwrap_or_else(|e| e), FEEDBACK_DURATION));
+        },
+        _ => ()
+    }
+}
 
-fn draw_board(ctx: &mut Context, game: &mut Game) -> GameResult<()> {
-    let mut mb = MeshBuilder::new();
 
-    // the 2 HP bars
-    mb.rectangle(
-        *FILL_MODE,
-        Rect::new(0.0, 0.0, HP_BAR_WIDTH, WINDOW_Y), 
-        LIFE_COLORS[game.player1.life_color_index] 
-    )?;
-    mb.rectangle(
-        *FILL_MODE,
-        Rect::new(WINDOW_X - HP_BAR_WIDTH, 0.0, HP_BAR_WIDTH, WINDOW_Y), 
-        LIFE_COLORS[game.player2.life_color_index] 
-    )?;
+fn draw_board(ctx: &mut Context, app: &App) -> GameResult<()> {
+    let (game, feedback) = (&app.game, &app.feedback);
+    let mut mb = MeshBuilder::new();
 
-    // the board
-    for y in 0..VERTICAL_BLOCKS {
-        for x in 0..HORIZONTAL_BLOCKS {
-            let color = if game.board[y][x] { Color::WHITE} else {Color::BLACK};
+    // the 2 HP bars, filled from the bottom in proportion to the HP left
+    for (player, bar_x) in [(&game.player1, 0.0), (&game.player2, WINDOW_X - HP_BAR_WIDTH)].iter() {
+        let fill_height = WINDOW_Y * hp_fraction(player);
+        mb.rectangle(
+            *FILL_MODE,
+            Rect::new(*bar_x, 0.0, HP_BAR_WIDTH, WINDOW_Y), 
+            Color::from_rgb(60, 60, 60)
+        )?;
+        if fill_height > 0.0 {
+            mb.rectangle(
+                *FILL_MODE,
+                Rect::new(*bar_x, WINDOW_Y - fill_height, HP_BAR_WIDTH, fill_height), 
+                hp_color(player)
+            )?;
+        }
+        // the commit cooldown, shrinking towards the top as it runs out
+        if player.movement_cooldown_time > 0.0 {
+            let cooldown_height = WINDOW_Y * (player.movement_cooldown_time / game.settings.commit_cooldown).min(1.0);
             mb.rectangle(
                 *FILL_MODE,
-                Rect::new(HP_BAR_WIDTH + x as f32 * BLOCK_SIZE, y as f32 * BLOCK_SIZE, BLOCK_SIZE, BLOCK_SIZE), 
-                color
+                Rect::new(*bar_x, 0.0, HP_BAR_WIDTH, cooldown_height), 
+                Color::new(1.0, 1.0, 1.0, 0.4)
             )?;
         }
     }
 
+    // the board
+    let layout = Layout::new(&game.board);
+    for y in 0..game.board.height() {
+        for x in 0..game.board.width() {
+            let color = match game.board.get(x, y) {
+                Some(owner) => player_color(&owner),
+                None => Color::BLACK
+            };
+            layout.block(&mut mb, *FILL_MODE, x, y, color)?;
+        }
+    }
+
     // selectable square area bounds
-    mb.rectangle(
-        *STROKE_MODE_1,
-        Rect::new(AREA_1_X, BLOCK_SIZE,AREA_WIDTH,AREA_LENGTH),
-        Color::from_rgb(105, 105, 105)
-    )?;
-    mb.rectangle(
-        *STROKE_MODE_1,
-        Rect::new(AREA_2_X, BLOCK_SIZE, AREA_WIDTH, AREA_LENGTH),
-        Color::from_rgb(105, 105, 105)
-    )?;
+    for player in [&game.player1, &game.player2].iter() {
+        let (left, right, upper, lower) = player.area_bounds();
+        mb.rectangle(
+            *STROKE_MODE_1,
+            layout.blocks(left, upper, right - left + 1, lower - upper + 1),
+            Color::from_rgb(105, 105, 105)
+        )?;
+    }
 
     // player selected squares
     let mut draw_selected_square = |player: &Player| -> GameResult{
         for p in player.selected_squares.iter() {
             let color = {
-                if game.board[p.y][p.x] {
+                if game.board.is_alive(p.x, p.y) {
                     Color::from_rgb(202, 239, 255)
                 } else {
                     Color::from_rgb(0, 35, 42)
                 }
             };
-            mb.rectangle(
-                *FILL_MODE,
-                Rect::new(p.x as f32 * BLOCK_SIZE + HP_BAR_WIDTH, p.y as f32 * BLOCK_SIZE, BLOCK_SIZE, BLOCK_SIZE),
-                color
-            )?;
-            mb.rectangle(
-                *STROKE_MODE_1,
-                Rect::new(p.x as f32 * BLOCK_SIZE + HP_BAR_WIDTH, p.y as f32 * BLOCK_SIZE, BLOCK_SIZE, BLOCK_SIZE),
-                Color::from_rgb(94, 199, 255)
-            )?;
+            layout.block(&mut mb, *FILL_MODE, p.x, p.y, color)?;
+            layout.block(&mut mb, *STROKE_MODE_1, p.x, p.y, Color::from_rgb(94, 199, 255))?;
         }
 
         Ok(())
@@ -295,21 +513,31 @@ fn draw_board(ctx: &mut Context, game: &mut Game) -> GameResult<()> {
     draw_selected_square(&game.player1)?;
     draw_selected_square(&game.player2)?;
 
+    // where the players' patterns would be stamped
+    for player_num in [PlayerNum::ONE, PlayerNum::TWO].iter() {
+        for p in game.stamp_squares(player_num).iter() {
+            layout.block(&mut mb, *STROKE_MODE_1, p.x, p.y, Color::from_rgb(120, 120, 120))?;
+        }
+    }
+
  
+    // what the players' selection tools would select, from their anchors
+    for player in [&game.player1, &game.player2].iter() {
+        for p in player.tool_squares().iter() {
+            layout.block(&mut mb, *STROKE_MODE_1, p.x, p.y, Color::from_rgb(94, 199, 255))?;
+        }
+        if let Some(anchor) = player.selection_anchor {
+            layout.block(&mut mb, *FILL_MODE, anchor.x, anchor.y, Color::from_rgb(94, 199, 255))?;
+        }
+    }
+
     // player hovering squares
-    mb.rectangle(
-        *STROKE_MODE_1,
-        Rect::new(game.player1.hovering_square.x as f32 * BLOCK_SIZE + HP_BAR_WIDTH, game.player1.hovering_square.y as f32 * BLOCK_SIZE, BLOCK_SIZE, BLOCK_SIZE),
-        Color::from_rgb(255, 94, 207)
-    )?;
-    mb.rectangle(
-        *STROKE_MODE_1,
-        Rect::new(game.player2.hovering_square.x as f32 * BLOCK_SIZE + HP_BAR_WIDTH, game.player2.hovering_square.y as f32 * BLOCK_SIZE, BLOCK_SIZE, BLOCK_SIZE),
-        Color::from_rgb(255, 94, 207)
-    )?;
+    for player in [&game.player1, &game.player2].iter() {
+        layout.block(&mut mb, *STROKE_MODE_1, player.hovering_square.x, player.hovering_square.y, Color::from_rgb(255, 94, 207))?;
+    }
 
     // debug line
-    // for i in 0..HORIZONTAL_BLOCKS {
+    // for i in 0..game.board.width() {
     //     let mut color = Color::from_rgb(60, 60, 60);
     //     if i % 2 == 1 {
     //         color = Color::from_rgb(190, 190, 190);
@@ -317,24 +545,131 @@ fn draw_board(ctx: &mut Context, game: &mut Game) -> GameResult<()> {
     //     if (i + 1) % 5 == 0 && i != 0 {
     //         color = Color::from_rgb(120, 150, 56);
     //     }
-    //     mb.rectangle(
-    //         *FILL_MODE,
-    //         Rect::new(HP_BAR_WIDTH +(i as f32 * BLOCK_SIZE), 200.0, BLOCK_SIZE, BLOCK_SIZE),
-    //         color
-    //     )?;
+    //     layout.block(&mut mb, *FILL_MODE, i, 7, color)?;
     // }
 
     let mesh = &mb.build(ctx)?;
 
     graphics::draw(ctx, mesh, DrawParam::default())?;
+
+    // the time left in a timed match
+    if let Some(time_left) = game.time_left() {
+        let seconds = time_left.ceil() as u32;
+        let clock = graphics::Text::new(format!("{}:{:02}", seconds / 60, seconds % 60))
+                .set_bounds(pointf![100.0, 30.0], graphics::Align::Center)
+                .set_font(graphics::Font::default(), PxScale{x: 24.0, y: 24.0 })
+                .to_owned();
+        graphics::draw(
+            ctx, 
+            &clock,
+            DrawParam::default().dest(pointf![WINDOW_X/2.0 - 50.0, 4.0]).color(Color::from_rgb(224, 142, 40))
+        )?;
+    }
+
+    // the speed, when it is not the one the match started with, whether time is frozen, and what the last export did
+    let mut status = Vec::new();
+    if game.generation_ticks != game.settings.generation_ticks() {
+        status.push(format!("speed x{:.2}", game.speed()));
+    }
+    if game.state == GameState::FROZEN {
+        status.push("frozen - N to step, [ ] to scrub, F to resume".to_string());
+        if game.history.rewound() > 0 {
+            status.push(format!("{} generations back", game.history.rewound()));
+        }
+    }
+    if let Some(message) = shown_message(app) {
+        status.push(message.to_string());
+    }
+    if !status.is_empty() {
+        let status = graphics::Text::new(status.join("  |  "))
+                .set_bounds(pointf![900.0, 200.0], graphics::Align::Center)
+                .set_font(graphics::Font::default(), PxScale{x: 20.0, y: 20.0 })
+                .to_owned();
+        // growing upwards from the bottom of the window, for messages longer than a line
+        let status_y = WINDOW_Y - 6.0 - status.height(ctx);
+        graphics::draw(
+            ctx, 
+            &status,
+            DrawParam::default().dest(pointf![WINDOW_X/2.0 - 450.0, status_y]).color(Color::from_rgb(224, 142, 40))
+        )?;
+    }
+
+    // the HP readouts and the commit cooldown left, on top of the HP bars
+    for (player, bar_x) in [(&game.player1, 0.0), (&game.player2, WINDOW_X - HP_BAR_WIDTH)].iter() {
+        let mut readout = player.hp.to_string();
+        if player.movement_cooldown_time > 0.0 {
+            readout += &format!("\n{:.1}", player.movement_cooldown_time);
+        }
+        let hp = graphics::Text::new(readout)
+                .set_bounds(pointf![HP_BAR_WIDTH, 40.0], graphics::Align::Center)
+                .set_font(graphics::Font::default(), PxScale{x: 12.0, y: 12.0 })
+                .to_owned();
+        graphics::draw(
+            ctx, 
+            &hp,
+            DrawParam::default().dest(pointf![*bar_x, 4.0]).color(Color::WHITE)
+        )?;
+    }
+
+    // the energy and the pattern of each player next to their HP bar, and why their last commit was rejected, for a while
+    for (i, (player, text_x, align)) in [(&game.player1, HP_BAR_WIDTH + 6.0, graphics::Align::Left),
+                                         (&game.player2, WINDOW_X - HP_BAR_WIDTH - 306.0, graphics::Align::Right)].iter().enumerate() {
+        let mut lines = format!("energy {}/{}\nselection tool: {}", player.energy.floor(), player.max_energy, player.selection_tool);
+        if player.dragging_selection {
+            lines += ", dragging the selection";
+        }
+        let saved_slots: Vec<String> = player.selection_slots.iter().enumerate()
+            .filter(|(_, slot)| slot.is_some())
+            .map(|(i, _)| (i + 1).to_string())
+            .collect();
+        if !saved_slots.is_empty() {
+            lines += &format!("\nsaved selections: {}", saved_slots.join(" "));
+        }
+        if let Some(pattern) = game.player_pattern(&player.player_num) {
+            let mirrored = if player.orientation.mirrored {", mirrored"} else {""};
+            lines += &format!("\npattern: {} ({}°{})", pattern.name, player.orientation.degrees(game.board.grid()), mirrored);
+            let other_grid = pattern.grid.is_some_and(|grid| grid != game.board.grid());
+            if let Some(rule) = pattern.rule.filter(|rule| *rule != game.settings.rule || other_grid) {
+                lines += &format!(" made for {} on a {} grid", rule, pattern.grid.unwrap_or_default());
+            }
+        }
+        if let Some((error, time)) = &feedback[i] {
+            if time.elapsed().as_secs_f32() < FEEDBACK_DURATION {
+                lines += &format!("\n{}", error);
+            }
+        }
+        let energy = graphics::Text::new(lines)
+                .set_bounds(pointf![300.0, 120.0], *align)
+                .set_font(graphics::Font::default(), PxScale{x: 18.0, y: 18.0 })
+                .to_owned();
+        graphics::draw(
+            ctx, 
+            &energy,
+            DrawParam::default().dest(pointf![*text_x, 4.0]).color(Color::from_rgb(224, 142, 40))
+        )?;
+    }
     
     Ok(())
 }
 
-fn draw_pause_menu(ctx: &mut Context) -> GameResult<()> {
+fn hp_fraction(player: &Player) -> f32 {
+    player.hp as f32 / player.max_hp as f32
+}
+
+// Blends between the LIFE_COLORS according to how much HP the player has lost.
+fn hp_color(player: &Player) -> Color {
+    let position = (1.0 - hp_fraction(player)) * (LIFE_COLORS.len() - 1) as f32;
+    let index = (position.floor() as usize).min(LIFE_COLORS.len() - 2);
+    let t = position - index as f32;
+    let (from, to) = (LIFE_COLORS[index], LIFE_COLORS[index + 1]);
+    Color::new(from.r + (to.r - from.r) * t, from.g + (to.g - from.g) * t, from.b + (to.b - from.b) * t, 1.0)
+}
+
+fn draw_pause_menu(ctx: &mut Context, app: &App) -> GameResult<()> {
+    let game = &app.game;
     let mut mb = MeshBuilder::new();
 
-    let (menu_x, menu_y, menu_width, menu_height) = (WINDOW_X/4.0, 100.0, WINDOW_X/2.0, 400.0);
+    let (menu_x, menu_y, menu_width, menu_height) = (WINDOW_X/4.0, 100.0, WINDOW_X/2.0, 850.0);
 
     mb.rounded_rectangle(
         *FILL_MODE,
@@ -347,6 +682,19 @@ fn draw_pause_menu(ctx: &mut Context) -> GameResult<()> {
 
     graphics::draw(ctx, mesh, DrawParam::default())?;
 
+    // what went wrong loading the settings and the patterns, above the menu
+    if let Some(message) = shown_message(app) {
+        let message = graphics::Text::new(message)
+                .set_bounds(pointf![menu_width, 96.0], graphics::Align::Center)
+                .set_font(graphics::Font::default(), PxScale{x: 18.0, y: 18.0 })
+                .to_owned();
+        graphics::draw(
+            ctx, 
+            &message,
+            DrawParam::default().dest(pointf![menu_x, 4.0]).color(Color::from_rgb(219, 68, 46))
+        )?;
+    }
+
     let title = graphics::Text::new("Fight for your life!")
             .set_bounds(pointf![menu_width,100.0], graphics::Align::Center)
             .set_font(graphics::Font::default(), PxScale{x: 40.0, y: 40.0 })
@@ -379,11 +727,20 @@ fn draw_pause_menu(ctx: &mut Context) -> GameResult<()> {
     )?;
 
     let keys = graphics::Text::new("move highlighted tile :  W A S D - (Player1) , Arrows (Player2)\n
+move diagonally :  Q E Z X - (Player1) , Home PgUp End PgDn (Player2)\n
 select/deselect tile : C - (Player1) , Shift - (Player2)\n
 faster movement: hold Alt - (Player1) , hold Ctrl - (Player2)\n
 finilize selected tiles : Space - (Player1) , Enter - (Player2)\n
+speed : - slower , = faster , 0 normal   |   freeze : F , step a generation while frozen : N\n
+scrub back/forward through past generations while frozen : [ ]\n
+patterns : stamp V , next/previous G/Shift+G , rotate T , mirror Y - (Player1) , stamp / , next/previous . , , rotate ; , mirror ' - (Player2)\n
+selection tools : start/finish H , next tool U , clear selection J - (Player1) , Numpad0 , NumpadEnter , Numpad. - (Player2)\n
+selection : rotate I , mirror left/right O , mirror up/down K , drag with the cursor L - (Player1) , Numpad/ , Numpad* , Numpad- , Numpad+ - (Player2)\n
+saved selections : recall 1-5 , save Alt+1-5 - (Player1) , recall Numpad1-5 , save Ctrl+Numpad1-5 - (Player2)\n
+save as a pattern : F2 the board , F3 the area of Player1 , F4 the area of Player2\n
+pattern editor : Tab\n
 Restart: R")
-            .set_bounds(pointf![menu_width - 10.0,200.0], graphics::Align::Left)
+            .set_bounds(pointf![menu_width - 10.0,630.0], graphics::Align::Left)
             .set_font(graphics::Font::default(), PxScale{x: 22.0, y: 22.0 })
             .to_owned();
     graphics::draw(
@@ -392,25 +749,93 @@ Restart: R")
         DrawParam::default().dest(pointf![menu_x + 5.0, menu_y + 185.0])
     )?;
 
+    let rule = &game.settings.rule;
+    let rule_name = match rule.name() {
+        Some(name) => format!("{} ({})", name, rule),
+        None => rule.to_string()
+    };
+    let rule_text = graphics::Text::new(format!("rule: {}, board: {} {}", rule_name, game.board.grid(), game.board.topology()))
+            .set_bounds(pointf![menu_width - 10.0,40.0], graphics::Align::Center)
+            .set_font(graphics::Font::default(), PxScale{x: 20.0, y: 20.0 })
+            .to_owned();
+    graphics::draw(
+        ctx, 
+        &rule_text,
+        DrawParam::default().dest(pointf![menu_x + 5.0, menu_y + 815.0]).color(Color::from_rgb(224, 142, 40))
+    )?;
+
+    Ok(())
+}
+
+fn draw_editor(ctx: &mut Context, app: &App) -> GameResult<()> {
+    let (game, editor) = (&app.game, &app.game.editor);
+    let mut mb = MeshBuilder::new();
+
+    // the canvas, or how it looks some generations later
+    let preview = editor.preview(&game.settings.rule);
+    let layout = Layout::new(&preview);
+    for y in 0..preview.height() {
+        for x in 0..preview.width() {
+            let color = match preview.get(x, y) {
+                Some(owner) => player_color(&owner),
+                None => Color::BLACK
+            };
+            layout.block(&mut mb, *FILL_MODE, x, y, color)?;
+        }
+    }
+    mb.rectangle(
+        *STROKE_MODE_1,
+        layout.blocks(0, 0, preview.width(), preview.height()),
+        Color::from_rgb(105, 105, 105)
+    )?;
+    layout.block(&mut mb, *STROKE_MODE_1, editor.cursor.x, editor.cursor.y, Color::from_rgb(255, 94, 207))?;
+
+    let mesh = &mb.build(ctx)?;
+    graphics::draw(ctx, mesh, DrawParam::default())?;
+
+    let mut lines = format!("pattern editor  |  name: {}_  |  ", editor.name);
+    if editor.preview_generations == 0 {
+        lines += "painting";
+    } else {
+        lines += &format!("{} generations later", editor.preview_generations);
+    }
+    lines += "\narrows move, Space paints, PgUp/PgDn preview, Home back to painting, Delete clears, type a name, Enter saves, Tab back";
+    if let Some(message) = shown_message(app) {
+        lines += &format!("\n{}", message);
+    }
+    let text = graphics::Text::new(lines)
+            .set_bounds(pointf![INNER_X, 80.0], graphics::Align::Center)
+            .set_font(graphics::Font::default(), PxScale{x: 18.0, y: 18.0 })
+            .to_owned();
+    graphics::draw(
+        ctx, 
+        &text,
+        DrawParam::default().dest(pointf![HP_BAR_WIDTH, 4.0]).color(Color::from_rgb(224, 142, 40))
+    )?;
+
     Ok(())
 }
 
 fn draw_winner_screen(ctx: &mut Context, game: &Game) -> GameResult<()> {
     let mut mb = MeshBuilder::new();
 
+    let (background, title) = match game.outcome {
+        Some(Outcome::WIN(PlayerNum::ONE)) => (Color::from_rgb(106, 181, 98), "Congratulations Player 1!"),
+        Some(Outcome::WIN(PlayerNum::TWO)) => (Color::from_rgb(106, 181, 98), "Congratulations Player 2!"),
+        Some(Outcome::DRAW) => (Color::from_rgb(98, 128, 181), "It's a draw!"),
+        None => (Color::from_rgb(106, 181, 98), "No winner yet!")
+    };
     mb.rectangle(
         *FILL_MODE,
         Rect::new(0.0, 0.0, WINDOW_X, WINDOW_Y),
-        Color::from_rgb(106, 181, 98)
+        background
     )?;
 
     let mesh = &mb.build(ctx)?;
 
     graphics::draw(ctx, mesh, DrawParam::default())?;
 
-    let winner = game.winner.clone().unwrap();
-    let player_name = if winner == PlayerNum::ONE {"Player 1!"} else {"Player 2!"};
-    let title = graphics::Text::new("Congratulations ".to_string() + player_name)
+    let title = graphics::Text::new(title)
     .set_bounds(pointf![600.0,100.0], graphics::Align::Center)
     .set_font(graphics::Font::default(), PxScale{x: 65.0, y: 65.0 })
     .to_owned();
@@ -432,253 +857,157 @@ fn draw_winner_screen(ctx: &mut Context, game: &Game) -> GameResult<()> {
         DrawParam::default().dest(pointf![WINDOW_X/4.0 + 150.0, 280.0])
     )?;
 
-    Ok(())
-}
-
-//1) Any live cell with fewer than two live neighbours dies, as if by underpopulation.
-//2) Any live cell with two or three live neighbours lives on to the next generation.
-//3) Any live cell with more than three live neighbours dies, as if by overpopulation.
-//4) Any dead cell with exactly three live neighbours becomes a live cell, as if by reproduction.
-fn calculate_next_generation(board: &mut BoardType) -> (BoardType,(bool,bool)) {
-    let mut next_gen_board = [[false; HORIZONTAL_BLOCKS]; VERTICAL_BLOCKS];
-    for (y,line) in board.iter().enumerate() {
-        for (x, cell) in line.iter().enumerate() {
-            let alive_neighbours = count_alive_neighbours(x,y,board);
-            if *cell {
-                if alive_neighbours == 3 || alive_neighbours == 2 {
-                    next_gen_board[y][x] = true;
-                }
-            } else {
-                if alive_neighbours == 3 {
-                    next_gen_board[y][x] = true;
-                }
-            }
-        }
+    for (i, player) in [&game.player1, &game.player2].iter().enumerate() {
+        let stats = graphics::Text::new(format!("Player {}: {} cells placed, {} hits dealt, {} own hits, {} cells alive",
+                                                i + 1, player.stats.cells_placed, player.stats.hits_dealt, player.stats.own_hits,
+                                                game.board.count_owned(&player.player_num)))
+        .set_bounds(pointf![800.0,40.0], graphics::Align::Center)
+        .set_font(graphics::Font::default(), PxScale{x: 22.0, y: 22.0 })
+        .to_owned();
+
+        graphics::draw(
+            ctx, 
+            &stats,
+            DrawParam::default().dest(pointf![WINDOW_X/2.0 - 400.0, 380.0 + i as f32 * 40.0]).color(player_color(&player.player_num))
+        )?;
     }
 
-    (next_gen_board, check_for_damage(board))
+    Ok(())
 }
 
-fn count_alive_neighbours(x: usize, y: usize, board: &BoardType) -> usize {
-    let mut count = 0;
-    if y == 0 || y == VERTICAL_BLOCKS - 1 || x == 0 || x == HORIZONTAL_BLOCKS - 1 {
-        if y == 0 {
-            if x == 0 {
-                if board[y+1][x]   {count += 1}
-                if board[y+1][x+1] {count += 1}
-                if board[y][x+1]   {count += 1}
-            } else if x == HORIZONTAL_BLOCKS - 1 {
-                if board[y+1][x]   {count += 1}
-                if board[y+1][x-1] {count += 1}
-                if board[y][x-1]   {count += 1}
-            } else {
-                if board[y+1][x-1] {count += 1}
-                if board[y+1][x]   {count += 1}
-                if board[y+1][x+1] {count += 1}
-                if board[y][x-1]   {count += 1}
-                if board[y][x+1]   {count += 1}
-            }
-            return count
-        } else if y == VERTICAL_BLOCKS - 1 {
-            if x == 0 {
-                if board[y-1][x]   {count += 1}
-                if board[y-1][x+1] {count += 1}
-                if board[y][x+1]   {count += 1}
-            } else if x == HORIZONTAL_BLOCKS - 1 {
-                if board[y-1][x]   {count += 1}
-                if board[y-1][x-1] {count += 1}
-                if board[y][x-1]   {count += 1}
-            } else {
-                if board[y-1][x-1] {count += 1}
-                if board[y-1][x]   {count += 1}
-                if board[y-1][x+1] {count += 1}
-                if board[y][x-1]   {count += 1}
-                if board[y][x+1]   {count += 1}
-            }
-            return count
-        } 
-
-        if x == 0 {
-            if board[y-1][x+1] {count += 1}
-            if board[y][x+1]   {count += 1}
-            if board[y+1][x+1] {count += 1}
-            if board[y-1][x]   {count += 1}
-            if board[y+1][x]   {count += 1}
-            return count
-        } else if x == HORIZONTAL_BLOCKS - 1 {
-            if board[y-1][x-1] {count += 1}
-            if board[y][x-1]   {count += 1}
-            if board[y+1][x-1] {count += 1}
-            if board[y-1][x]   {count += 1}
-            if board[y+1][x]   {count += 1}
-            return count
-        } 
-    } else { // is not near a corner
-        if board[y-1][x-1] {count += 1}
-        if board[y-1][x]   {count += 1}
-        if board[y-1][x+1] {count += 1}
-        
-        if board[y+1][x-1] {count += 1}
-        if board[y+1][x]   {count += 1}
-        if board[y+1][x+1] {count += 1}
-
-        if board[y][x+1] {count += 1}
-        if board[y][x-1] {count += 1}
+fn player_color(player_num: &PlayerNum) -> Color {
+    match player_num {
+        PlayerNum::ONE => Color::from_rgb(120, 210, 255),
+        PlayerNum::TWO => Color::from_rgb(255, 176, 92)
     }
-
-    count
 }
 
-fn check_for_damage(board: &BoardType) -> (bool,bool) {
-    let (mut player1_damage, mut player2_damage) = (false,false);
-    let (mut consecutive_alive_count_1, mut consecutive_alive_count_2) = (0,0);
-    for row in board.iter() {
-        if !player1_damage {
-            if row[0] {
-                consecutive_alive_count_1 += 1;
-                if consecutive_alive_count_1 == 3 {
-                    player1_damage = true;
-                    break;
-                }
-            }
-        }
+// The settings file is looked up next to the executable. A missing file means default settings.
+fn next_to_executable(name: &str) -> PathBuf {
+    std::env::current_exe().ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(name)))
+        .unwrap_or_else(|| PathBuf::from(name))
+}
 
-        if !player2_damage {
-            if row[HORIZONTAL_BLOCKS - 1] {
-                consecutive_alive_count_2 += 1;
-                if consecutive_alive_count_2 == 3 {
-                    player2_damage = true;
-                    break;
-                }
+// The settings in settings.cfg, or the default ones, with why, when the file cannot be parsed.
+fn load_settings() -> (MatchSettings, Option<String>) {
+    let path = next_to_executable(SETTINGS_FILE_NAME);
+    match std::fs::read_to_string(&path) {
+        Ok(text) => match MatchSettings::parse(&text) {
+            Ok(settings) => (settings, None),
+            Err(e) => {
+                println!("{}: {}, using the default settings", path.display(), e);
+                (MatchSettings::default(), Some(format!("{}: {}, using the default settings", SETTINGS_FILE_NAME, e)))
             }
-        }
-
-        if player1_damage && player2_damage {
-            return (true, true)
-        }
-    } 
-
-    (player1_damage,player2_damage)
+        },
+        Err(_) => (MatchSettings::default(), None)
+    }
 }
 
-fn make_damage_calculations(game: &mut Game, players_damage: (bool,bool)) {
-    if players_damage.0 {
-        game.player1.take_damage()
-    }
-    if players_damage.1 {
-        game.player2.take_damage()
-    }
+// The settings and the pattern library for the next match, and what went wrong loading them, if anything.
+fn load_match_files() -> (MatchSettings, Vec<Pattern>, Option<String>) {
+    let (settings, settings_problem) = load_settings();
+    let (patterns, skipped) = load_patterns();
 
-    if game.player1.is_dead() {
-        println!("player 2 won");
-        game.winner = Some(PlayerNum::TWO);
-        game.state = GameState::WINNER_SCREEN;
+    let mut problems: Vec<String> = settings_problem.into_iter().collect();
+    if !skipped.is_empty() {
+        let files = if skipped.len() == 1 {"file"} else {"files"};
+        problems.push(format!("{} pattern {} skipped: {}", skipped.len(), files, skipped.join(", ")));
     }
-    if game.player2.is_dead() {
-        println!("player 1 won");
-        game.winner = Some(PlayerNum::ONE);
-        game.state = GameState::WINNER_SCREEN;
-    } 
+    (settings, patterns, if problems.is_empty() {None} else {Some(problems.join("\n"))})
 }
 
+fn timed_message(message: String, seconds: f32) -> (String, Instant) {
+    (message, Instant::now() + Duration::from_secs_f32(seconds))
+}
 
-impl Player {
-    pub fn new(player_num: PlayerNum) -> Self {
-        let _x_left_bound = match player_num {
-            PlayerNum::ONE => (AREA_1_X / BLOCK_SIZE) as usize,
-            PlayerNum::TWO => (AREA_2_X / BLOCK_SIZE) as usize
-        };
-        let _x_right_bound = _x_left_bound + (AREA_WIDTH / BLOCK_SIZE) as usize - 1;
-        let _y_upper_bound = 1usize;
-        let _y_lower_bound = VERTICAL_BLOCKS - 2;
-
-        let hovering_square_point = match player_num {
-            PlayerNum::ONE => pointu![(AREA_1_X + AREA_WIDTH/2.0) as usize / BLOCK_SIZE as usize, (VERTICAL_BLOCKS/2)],
-            PlayerNum::TWO => pointu![(AREA_2_X + AREA_WIDTH/2.0) as usize / BLOCK_SIZE as usize, (VERTICAL_BLOCKS/2)]
-        };
-
-        Player {
-            player_num,
-            movement_cooldown_time: 0.0,
-            life_color_index: 0,
-            hovering_square : hovering_square_point,
-            selected_squares: Vec::with_capacity(20),
-            _x_left_bound,
-            _x_right_bound,
-            _y_upper_bound,
-            _y_lower_bound
-        }
-    }
-
-    pub fn is_dead(&self) -> bool {
-        self.life_color_index == LIFE_COLORS.len() - 1
-    }
+fn shown_message(app: &App) -> Option<&str> {
+    app.message.as_ref().filter(|(_, until)| Instant::now() < *until).map(|(message, _)| message.as_str())
+}
 
-    pub fn take_damage(&mut self) {
-        self.life_color_index +=1; 
+// Writes the (x, y, width, height) region of the board to a new RLE file in the patterns directory, named after `name`
+// and the time, and adds it to the pattern library. Returns what happened.
+fn export_region(game: &mut Game, name: &str, (x, y, width, height): (usize, usize, usize, usize)) -> String {
+    let seconds = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    let base_name = format!("{}-{}", name, seconds);
+    let mut pattern = Pattern::from_board(&base_name, &game.board, x, y, width, height, game.settings.rule);
+    if pattern.cells.is_empty() {
+        return "nothing to export, there are no alive cells".to_string()
     }
 
-    pub fn move_hover(&mut self, dir: Direction, mut amount: usize) {
-        match dir {
-            Direction::UP => {
-                if amount > self.hovering_square.y {amount = self.hovering_square.y};
-                if self.hovering_square.y - amount < self._y_upper_bound {
-                    self.hovering_square.y = self._y_lower_bound;
-                } else {
-                    self.hovering_square.y -= amount; 
-                }
-            },
-            Direction::RIGHT => {
-                if self.hovering_square.x + amount > self._x_right_bound {
-                    self.hovering_square.x = self._x_left_bound;
-                } else {
-                    self.hovering_square.x += amount; 
-                }
+    let dir = next_to_executable(PATTERNS_DIR_NAME);
+    // exports from the same second get a number after the time instead of overwriting each other
+    let mut copy = 1;
+    let written = loop {
+        let path = dir.join(format!("{}.rle", pattern.name));
+        match std::fs::create_dir_all(&dir).and_then(|_| std::fs::OpenOptions::new().write(true).create_new(true).open(&path)) {
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                copy += 1;
+                pattern.name = format!("{}-{}", base_name, copy);
             },
-            Direction::DOWN => {
-                if self.hovering_square.y + amount > self._y_lower_bound {
-                    self.hovering_square.y = self._y_upper_bound;
-                } else {
-                    self.hovering_square.y += amount; 
-                }
-            },
-            Direction::LEFT => {
-                if amount > self.hovering_square.x {amount = self.hovering_square.y};
-                if self.hovering_square.x - amount < self._x_left_bound {
-                    self.hovering_square.x = self._x_right_bound;
-                } else {
-                    self.hovering_square.x -= amount; 
-                }
-            }
+            file => break file.and_then(|mut file| file.write_all(pattern.to_rle().as_bytes()))
+        }
+    };
+    let file_name = format!("{}.rle", pattern.name);
+    let path = dir.join(&file_name);
+    match written {
+        Ok(()) => {
+            game.patterns.push(pattern);
+            println!("saved {}", path.display());
+            format!("saved {}/{}", PATTERNS_DIR_NAME, file_name)
+        },
+        Err(e) => {
+            println!("could not save {}: {}", path.display(), e);
+            format!("could not save {}: {}", file_name, e)
         }
     }
 }
 
-impl Game {
-    pub fn new() -> Game {
-        Game {
-            state: GameState::PAUSE_MENU,
-            timer: 0.0,
-            last_update_time: Instant::now(),
-            player1:  Player::new(PlayerNum::ONE),
-            player2:  Player::new(PlayerNum::TWO),
-            winner: None,
-            board: [[false; HORIZONTAL_BLOCKS]; VERTICAL_BLOCKS]
+// Writes a pattern from the editor to the patterns directory, named after it. An existing file is never overwritten.
+fn save_pattern(pattern: &Pattern) -> Result<String, String> {
+    let file_name = format!("{}.rle", pattern.name);
+    let dir = next_to_executable(PATTERNS_DIR_NAME);
+    let path = dir.join(&file_name);
+    let file = std::fs::create_dir_all(&dir).and_then(|_| std::fs::OpenOptions::new().write(true).create_new(true).open(&path));
+    match file.and_then(|mut file| file.write_all(pattern.to_rle().as_bytes())) {
+        Ok(()) => {
+            println!("saved {}", path.display());
+            Ok(format!("saved {}/{}", PATTERNS_DIR_NAME, file_name))
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Err(format!("{} already exists, pick another name", file_name)),
+        Err(e) => {
+            println!("could not save {}: {}", path.display(), e);
+            Err(format!("could not save {}: {}", file_name, e))
         }
     }
-
-    pub fn reset(&mut self) {
-        self.state = GameState::PLAYING;
-        self.timer = 0.0;
-        self.player1 = Player::new(PlayerNum::ONE);
-        self.player2 = Player::new(PlayerNum::TWO);
-        self.winner = None;
-        self.board = [[false; HORIZONTAL_BLOCKS]; VERTICAL_BLOCKS]
-    }
 }
 
+// The built-in patterns followed by the ones in the patterns directory, in file name order,
+// and why each file that could not be read or parsed was skipped.
+fn load_patterns() -> (Vec<Pattern>, Vec<String>) {
+    let (mut patterns, mut skipped) = (Pattern::built_in(), Vec::new());
+    let mut paths: Vec<PathBuf> = match std::fs::read_dir(next_to_executable(PATTERNS_DIR_NAME)) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect(),
+        Err(_) => return (patterns, skipped)
+    };
+    paths.sort();
+
+    for path in paths.iter() {
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("").to_ascii_lowercase();
+        if extension != "rle" && extension != "cells" {continue}
+
+        let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+        match std::fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|text| Pattern::parse(file_name, &text).map_err(|e| e.to_string())) {
+            Ok(pattern) => patterns.push(pattern),
+            Err(e) => {
+                println!("{}: {}, skipping it", path.display(), e);
+                skipped.push(format!("{} {}", file_name, e));
+            }
+        }
+    }
 
-
+    (patterns, skipped)
+}
 
 fn main() {
     let (ctx, event_loop) = ContextBuilder::new("fight_for_your_life", "Petros Papatheodorou")
@@ -700,7 +1029,17 @@ fn main() {
         window.set_outer_position(pos);
     }
 
-    let game = Game::new();
+    let (settings, patterns, problems) = load_match_files();
+    let mut game = Game::new(settings);
+    game.patterns = patterns;
+    let message = problems.map(|problems| timed_message(problems, LOAD_PROBLEMS_DURATION));
+    let app = App {
+        game,
+        last_update_time: Instant::now(),
+        feedback: [None, None],
+        message,
+        printed_outcome: None
+    };
 
-    event::run(ctx, event_loop, game);
+    event::run(ctx, event_loop, app);
 }
\ No newline at end of file